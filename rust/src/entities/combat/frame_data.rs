@@ -0,0 +1,240 @@
+use godot::{
+    classes::{IResource, Resource},
+    global::godot_warn,
+    prelude::*,
+};
+
+pub const FRAME_DATA_PATH: &str = "res://resources/attack_frame_data.tres";
+
+/// The part of an attack a frame falls in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttackPhase {
+    Startup,
+    /// The hurtbox is enabled.
+    Active,
+    Recovery,
+}
+
+/// Frame counts of an attack, in physics frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameData {
+    pub startup: u32,
+    pub active: u32,
+    pub recovery: u32,
+    /// Frame from which the attack can be cancelled into a dodge or the next combo step, lasting
+    /// until the attack ends.
+    pub cancel_from: u32,
+}
+
+impl FrameData {
+    pub const fn new(startup: u32, active: u32, recovery: u32, cancel_from: u32) -> Self {
+        Self {
+            startup,
+            active,
+            recovery,
+            cancel_from,
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        self.startup + self.active + self.recovery
+    }
+
+    /// The phase of `frame`, counted from the first frame of the attack. `None` once the attack
+    /// is over.
+    pub fn phase(&self, frame: u32) -> Option<AttackPhase> {
+        if frame < self.startup {
+            Some(AttackPhase::Startup)
+        } else if frame < self.startup + self.active {
+            Some(AttackPhase::Active)
+        } else if frame < self.total() {
+            Some(AttackPhase::Recovery)
+        } else {
+            None
+        }
+    }
+}
+
+/// An attack in progress, advanced once per physics frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttackWindow {
+    data: FrameData,
+    frame: u32,
+}
+
+impl AttackWindow {
+    pub fn new(data: FrameData) -> Self {
+        Self { data, frame: 0 }
+    }
+
+    /// Moves to the next frame, returning `false` once the attack is over.
+    pub fn advance(&mut self) -> bool {
+        self.frame += 1;
+        self.frame < self.data.total()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.data.phase(self.frame) == Some(AttackPhase::Active)
+    }
+
+    pub fn can_cancel(&self) -> bool {
+        self.frame >= self.data.cancel_from
+    }
+}
+
+/// Frame data of every player attack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlayerFrameData {
+    pub attack: FrameData,
+    pub chain_attack: FrameData,
+    pub charged_attack: FrameData,
+    pub crouch_attack: FrameData,
+    pub air_attack: FrameData,
+}
+
+impl Default for PlayerFrameData {
+    fn default() -> Self {
+        Self {
+            attack: FrameData::new(4, 3, 5, 7),
+            chain_attack: FrameData::new(18, 6, 18, 30),
+            charged_attack: FrameData::new(6, 6, 24, 24),
+            crouch_attack: FrameData::new(8, 6, 11, 19),
+            air_attack: FrameData::new(4, 3, 3, 7),
+        }
+    }
+}
+
+/// Designer facing frame data for the player's attacks, at 60 physics frames per second.
+#[derive(GodotClass)]
+#[class(base = Resource)]
+pub struct AttackFrameData {
+    #[export_group(name = "Attack")]
+    #[export]
+    pub attack_startup: u32,
+    #[export]
+    pub attack_active: u32,
+    #[export]
+    pub attack_recovery: u32,
+    #[export]
+    pub attack_cancel_from: u32,
+
+    #[export_group(name = "Chain Attack")]
+    #[export]
+    pub chain_attack_startup: u32,
+    #[export]
+    pub chain_attack_active: u32,
+    #[export]
+    pub chain_attack_recovery: u32,
+    #[export]
+    pub chain_attack_cancel_from: u32,
+
+    #[export_group(name = "Charged Attack")]
+    #[export]
+    pub charged_attack_startup: u32,
+    #[export]
+    pub charged_attack_active: u32,
+    #[export]
+    pub charged_attack_recovery: u32,
+    #[export]
+    pub charged_attack_cancel_from: u32,
+
+    #[export_group(name = "Crouch Attack")]
+    #[export]
+    pub crouch_attack_startup: u32,
+    #[export]
+    pub crouch_attack_active: u32,
+    #[export]
+    pub crouch_attack_recovery: u32,
+    #[export]
+    pub crouch_attack_cancel_from: u32,
+
+    #[export_group(name = "Air Attack")]
+    #[export]
+    pub air_attack_startup: u32,
+    #[export]
+    pub air_attack_active: u32,
+    #[export]
+    pub air_attack_recovery: u32,
+    #[export]
+    pub air_attack_cancel_from: u32,
+
+    base: Base<Resource>,
+}
+
+#[godot_api]
+impl IResource for AttackFrameData {
+    fn init(base: Base<Resource>) -> Self {
+        let d = PlayerFrameData::default();
+        Self {
+            attack_startup: d.attack.startup,
+            attack_active: d.attack.active,
+            attack_recovery: d.attack.recovery,
+            attack_cancel_from: d.attack.cancel_from,
+            chain_attack_startup: d.chain_attack.startup,
+            chain_attack_active: d.chain_attack.active,
+            chain_attack_recovery: d.chain_attack.recovery,
+            chain_attack_cancel_from: d.chain_attack.cancel_from,
+            charged_attack_startup: d.charged_attack.startup,
+            charged_attack_active: d.charged_attack.active,
+            charged_attack_recovery: d.charged_attack.recovery,
+            charged_attack_cancel_from: d.charged_attack.cancel_from,
+            crouch_attack_startup: d.crouch_attack.startup,
+            crouch_attack_active: d.crouch_attack.active,
+            crouch_attack_recovery: d.crouch_attack.recovery,
+            crouch_attack_cancel_from: d.crouch_attack.cancel_from,
+            air_attack_startup: d.air_attack.startup,
+            air_attack_active: d.air_attack.active,
+            air_attack_recovery: d.air_attack.recovery,
+            air_attack_cancel_from: d.air_attack.cancel_from,
+            base,
+        }
+    }
+}
+
+impl AttackFrameData {
+    /// Loads the player's frame data, falling back to the defaults if it is missing.
+    pub fn load_or_default() -> PlayerFrameData {
+        match try_load::<Self>(FRAME_DATA_PATH) {
+            Ok(data) => data.bind().player_frame_data(),
+            Err(e) => {
+                godot_warn!("Failed to load attack frame data, using defaults: {e}");
+                PlayerFrameData::default()
+            }
+        }
+    }
+
+    pub fn player_frame_data(&self) -> PlayerFrameData {
+        PlayerFrameData {
+            attack: FrameData::new(
+                self.attack_startup,
+                self.attack_active,
+                self.attack_recovery,
+                self.attack_cancel_from,
+            ),
+            chain_attack: FrameData::new(
+                self.chain_attack_startup,
+                self.chain_attack_active,
+                self.chain_attack_recovery,
+                self.chain_attack_cancel_from,
+            ),
+            charged_attack: FrameData::new(
+                self.charged_attack_startup,
+                self.charged_attack_active,
+                self.charged_attack_recovery,
+                self.charged_attack_cancel_from,
+            ),
+            crouch_attack: FrameData::new(
+                self.crouch_attack_startup,
+                self.crouch_attack_active,
+                self.crouch_attack_recovery,
+                self.crouch_attack_cancel_from,
+            ),
+            air_attack: FrameData::new(
+                self.air_attack_startup,
+                self.air_attack_active,
+                self.air_attack_recovery,
+                self.air_attack_cancel_from,
+            ),
+        }
+    }
+}
@@ -1,4 +1,5 @@
 pub mod defense;
+pub mod frame_data;
 pub mod offense;
 pub mod resources;
 
@@ -29,7 +30,7 @@ mod test {
                     Resistance::Elemental(Element::Magic, 5),
                 ]),
                 resource: CombatResources::new(
-                    Health::new(10, 10, Heal::new(2)),
+                    Health::new(10, 10, Heal::new(2, 3)),
                     Stamina::new(30, 30),
                     Mana::new(15, 15),
                 ),
@@ -114,4 +115,29 @@ mod test {
 
         assert!(Offense::try_attack(PlayerAttacks::FireSpell, &mut attacker.resource, 1).is_err());
     }
+
+    #[test]
+    fn test_heal_charges_and_refill() {
+        let mut dummy = Dummy::new();
+        let attack = PlayerAttacks::SimpleMelee.build(2); // 20
+        let damage = dummy.defense.apply_resistances(&attack);
+        dummy.resource.take_damage(damage);
+        assert_eq!(dummy.resource.health().amount(), 0);
+
+        for _ in 0..3 {
+            dummy.resource.heal();
+        }
+        assert_eq!(dummy.resource.health().amount(), 6);
+        assert_eq!(dummy.resource.health().heal_charges(), 0);
+
+        // Out of charges.
+        dummy.resource.heal();
+        assert_eq!(dummy.resource.health().amount(), 6);
+
+        dummy.resource.refill();
+        assert_eq!(dummy.resource.health().amount(), 10);
+        assert_eq!(dummy.resource.health().heal_charges(), 3);
+        assert_eq!(dummy.resource.stamina().amount(), 30);
+        assert_eq!(dummy.resource.mana().amount(), 15);
+    }
 }
@@ -13,7 +13,7 @@ use crate::{
     utils::global_data_singleton::GlobalData,
 };
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Damage(pub i64);
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -176,21 +176,36 @@ impl Spell {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum HotSpellIndexer {
     Ability1,
     Ability2,
     Ability3,
 }
 
+impl HotSpellIndexer {
+    const ALL: [HotSpellIndexer; 3] = [
+        HotSpellIndexer::Ability1,
+        HotSpellIndexer::Ability2,
+        HotSpellIndexer::Ability3,
+    ];
+}
+
 #[derive(Clone, Debug)]
 pub struct Offense {
     buffs: Vec<Buff>,
     hot_spells: [Option<Spell>; 3],
+    /// The hot spell cast by the cast spell button.
+    selected_spell: HotSpellIndexer,
 }
 
 impl Offense {
     pub fn new(buffs: Vec<Buff>, hot_spells: [Option<Spell>; 3]) -> Self {
-        Self { buffs, hot_spells }
+        Self {
+            buffs,
+            hot_spells,
+            selected_spell: HotSpellIndexer::Ability1,
+        }
     }
 
     pub fn buffs(&self) -> &[Buff] {
@@ -231,6 +246,18 @@ impl Offense {
         self.hot_spells[idx as usize]
     }
 
+    pub fn selected_spell(&self) -> HotSpellIndexer {
+        self.selected_spell
+    }
+
+    /// Selects the next (`step > 0`) or previous hot spell slot, wrapping around.
+    pub fn rotate_spell(&mut self, step: i32) -> HotSpellIndexer {
+        let len = HotSpellIndexer::ALL.len() as i32;
+        let idx = (self.selected_spell as i32 + step).rem_euclid(len);
+        self.selected_spell = HotSpellIndexer::ALL[idx as usize];
+        self.selected_spell
+    }
+
     pub fn check_resources(
         costs: &[AttackResourceCost],
         resource: &mut CombatResources,
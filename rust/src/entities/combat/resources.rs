@@ -1,5 +1,13 @@
 use crate::entities::combat::offense::Damage;
 
+/// What caused damage to be taken.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DamageSource {
+    Attack,
+    Fall,
+    Drowning,
+}
+
 pub enum ResourceChanged {
     Stamina { previous: i64, new: i64 },
     Mana { previous: i64, new: i64 },
@@ -33,6 +41,11 @@ impl Resource {
     pub fn increase_max(&mut self, max: i64) {
         self.max = max;
     }
+
+    /// Sets the amount to the maximum.
+    pub fn refill(&mut self) {
+        self.amount = self.max;
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -51,11 +64,23 @@ impl Stamina {
     }
 }
 
+/// The amount healed per use and the number of uses available.
 #[derive(Clone, Copy, Debug)]
-pub struct Heal(i64);
+pub struct Heal {
+    amount: i64,
+    charges: Resource,
+}
+
 impl Heal {
-    pub fn new(amount: i64) -> Self {
-        Self(amount)
+    pub fn new(amount: i64, charges: i64) -> Self {
+        Self {
+            amount,
+            charges: Resource::new(charges, charges),
+        }
+    }
+
+    pub fn charges(&self) -> i64 {
+        self.charges.amount
     }
 }
 
@@ -77,8 +102,16 @@ impl Health {
         self.0.amount <= 0
     }
 
+    /// Consumes a heal charge, if one is available.
     pub fn heal(&mut self) {
-        self.0.increase(self.1.0);
+        if self.1.charges() > 0 {
+            self.1.charges.decrease(1);
+            self.0.increase(self.1.amount);
+        }
+    }
+
+    pub fn heal_charges(&self) -> i64 {
+        self.1.charges()
     }
 
     pub fn set_healing(&mut self, heal: Heal) {
@@ -120,6 +153,7 @@ pub struct CombatResources {
     stam_counter: f32,
     mana_counter: f32,
     health_counter: f32,
+    last_damage_source: Option<DamageSource>,
 }
 
 impl CombatResources {
@@ -131,6 +165,7 @@ impl CombatResources {
             stam_counter: 0.0,
             mana_counter: 0.0,
             health_counter: 0.0,
+            last_damage_source: None,
         }
     }
 
@@ -147,9 +182,19 @@ impl CombatResources {
     }
 
     pub fn take_damage(&mut self, damage: Damage) -> (i64, i64) {
+        self.take_damage_from(damage, DamageSource::Attack)
+    }
+
+    pub fn take_damage_from(&mut self, damage: Damage, source: DamageSource) -> (i64, i64) {
+        self.last_damage_source = Some(source);
         self.health.take_damage(damage)
     }
 
+    /// The source of the most recent damage taken.
+    pub fn last_damage_source(&self) -> Option<DamageSource> {
+        self.last_damage_source
+    }
+
     pub fn heal(&mut self) -> (i64, i64) {
         let cur = self.health.amount();
         self.health.heal();
@@ -196,6 +241,34 @@ impl CombatResources {
         }
     }
 
+    /// Restores health, stamina, mana and heal charges to their maximums.
+    pub fn refill(&mut self) -> [ResourceChanged; 3] {
+        let health = self.health.amount();
+        let stam = self.stam.amount();
+        let mana = self.mana.amount();
+        self.health.0.refill();
+        self.health.1.charges.refill();
+        self.stam.0.refill();
+        self.mana.0.refill();
+        self.stam_counter = 0.0;
+        self.mana_counter = 0.0;
+        self.health_counter = 0.0;
+        [
+            ResourceChanged::Health {
+                previous: health,
+                new: self.health.amount(),
+            },
+            ResourceChanged::Stamina {
+                previous: stam,
+                new: self.stam.amount(),
+            },
+            ResourceChanged::Mana {
+                previous: mana,
+                new: self.mana.amount(),
+            },
+        ]
+    }
+
     pub fn handle_attack_cost(&mut self, costs: &[AttackResourceCost]) -> Result<(), ()> {
         for cost in costs {
             match cost {
@@ -0,0 +1,32 @@
+use crate::entities::{
+    combat::{
+        defense::{Defense, Resistance},
+        offense::{Buff, Offense, PlayerAttacks},
+        resources::{CombatResources, Heal, Health, Mana, Stamina},
+    },
+    enemies::{
+        archetypes::{EnemyBehavior, EnemyStats},
+        enemy_core::EnemyCore,
+    },
+};
+
+/// Slow and tough, hits with its own hurtbox.
+pub struct Brute;
+
+impl EnemyBehavior for Brute {
+    fn stats(&self) -> EnemyStats {
+        EnemyStats {
+            resources: CombatResources::new(
+                Health::new(30, 30, Heal::new(5, 0)),
+                Stamina::new(50, 50),
+                Mana::new(0, 0),
+            ),
+            def: Defense::new(vec![Resistance::Physical(10)]),
+            off: Offense::new(vec![Buff::Physical(5)], [None, None, None]),
+        }
+    }
+
+    fn strike(&self, enemy: &mut EnemyCore) {
+        enemy.arm_hurtbox(PlayerAttacks::SimpleMelee);
+    }
+}
@@ -0,0 +1,54 @@
+use godot::{classes::PackedScene, tools::load};
+
+use crate::entities::{
+    combat::{
+        defense::{Defense, Resistance},
+        offense::{Buff, Element, Offense, PlayerAttacks},
+        resources::{CombatResources, Heal, Health, Mana, Stamina},
+    },
+    enemies::{
+        archetypes::{EnemyBehavior, EnemyStats},
+        enemy_core::EnemyCore,
+        projectile::Projectile,
+    },
+    hit_reg::Hurtbox,
+};
+
+const PROJECTILE_SCENE: &str = "res://world/projectile.tscn";
+
+/// Floats in place and shoots at the player.
+pub struct Caster;
+
+impl EnemyBehavior for Caster {
+    fn stats(&self) -> EnemyStats {
+        EnemyStats {
+            resources: CombatResources::new(
+                Health::new(10, 10, Heal::new(5, 0)),
+                Stamina::new(20, 20),
+                Mana::new(20, 20),
+            ),
+            def: Defense::new(vec![Resistance::Physical(5)]),
+            off: Offense::new(vec![Buff::Elemental(Element::Magic, 2)], [None, None, None]),
+        }
+    }
+
+    fn strike(&self, enemy: &mut EnemyCore) {
+        let Some(player_pos) = enemy.sensors.player_detection.player_position() else {
+            return;
+        };
+        let Ok(attack) = enemy.prepare_attack(PlayerAttacks::FireSpell) else {
+            return;
+        };
+        let pos = enemy.node().get_global_position();
+        let mut inst = load::<PackedScene>(PROJECTILE_SCENE).instantiate_as::<Projectile>();
+        let mut hurtbox = inst.get_node_as::<Hurtbox>("Hurtbox");
+        hurtbox.bind_mut().set_attack(attack);
+        inst.set_global_position(pos);
+        inst.bind_mut().target = pos.direction_to(player_pos).normalized_or_zero();
+        enemy.node().add_sibling(&inst);
+    }
+
+    fn flies(&self) -> bool {
+        true
+    }
+}
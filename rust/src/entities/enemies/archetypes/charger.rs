@@ -0,0 +1,45 @@
+use godot::builtin::Vector2;
+
+use crate::entities::{
+    combat::{
+        defense::{Defense, Resistance},
+        offense::{Buff, Offense, PlayerAttacks},
+        resources::{CombatResources, Heal, Health, Mana, Stamina},
+    },
+    enemies::{
+        archetypes::{EnemyBehavior, EnemyStats},
+        enemy_core::EnemyCore,
+    },
+};
+
+const CHARGE_SPEED: f32 = 450.0;
+
+/// Rushes horizontally at the player for the length of its attack.
+pub struct Charger;
+
+impl EnemyBehavior for Charger {
+    fn stats(&self) -> EnemyStats {
+        EnemyStats {
+            resources: CombatResources::new(
+                Health::new(20, 20, Heal::new(5, 0)),
+                Stamina::new(40, 40),
+                Mana::new(0, 0),
+            ),
+            def: Defense::new(vec![Resistance::Physical(5)]),
+            off: Offense::new(vec![Buff::Physical(10)], [None, None, None]),
+        }
+    }
+
+    fn strike(&self, enemy: &mut EnemyCore) {
+        let Some(player_pos) = enemy.sensors.player_detection.player_position() else {
+            return;
+        };
+        let dir = if player_pos.x < enemy.node().get_global_position().x {
+            Vector2::LEFT
+        } else {
+            Vector2::RIGHT
+        };
+        enemy.movement.charge(dir * CHARGE_SPEED);
+        enemy.arm_hurtbox(PlayerAttacks::SimpleMelee);
+    }
+}
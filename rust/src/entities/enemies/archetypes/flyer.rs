@@ -0,0 +1,40 @@
+use crate::entities::{
+    combat::{
+        defense::Defense,
+        offense::{Offense, PlayerAttacks},
+        resources::{CombatResources, Heal, Health, Mana, Stamina},
+    },
+    enemies::{
+        archetypes::{EnemyBehavior, EnemyStats},
+        enemy_core::EnemyCore,
+    },
+};
+
+/// Frail, but follows the player anywhere.
+pub struct Flyer;
+
+impl EnemyBehavior for Flyer {
+    fn stats(&self) -> EnemyStats {
+        EnemyStats {
+            resources: CombatResources::new(
+                Health::new(8, 8, Heal::new(5, 0)),
+                Stamina::new(30, 30),
+                Mana::new(0, 0),
+            ),
+            def: Defense::new(Vec::new()),
+            off: Offense::new(Vec::new(), [None, None, None]),
+        }
+    }
+
+    fn strike(&self, enemy: &mut EnemyCore) {
+        enemy.arm_hurtbox(PlayerAttacks::SimpleMelee);
+    }
+
+    fn flies(&self) -> bool {
+        true
+    }
+
+    fn chases_vertically(&self) -> bool {
+        true
+    }
+}
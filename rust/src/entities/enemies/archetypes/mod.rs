@@ -0,0 +1,95 @@
+use godot::prelude::{Export, GodotConvert, Var};
+
+use crate::entities::{
+    combat::{defense::Defense, offense::Offense, resources::CombatResources},
+    enemies::{enemy_core::EnemyCore, enemy_state_machine::State},
+};
+
+mod brute;
+mod caster;
+mod charger;
+mod flyer;
+
+/// The kinds of enemies. Exported on the enemy classes so a scene picks its behavior.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, GodotConvert, Var, Export)]
+#[godot(via = i64)]
+pub enum Archetype {
+    /// Walks up to the player and hits them.
+    #[default]
+    Brute,
+    /// Floats at a distance and shoots projectiles.
+    Caster,
+    /// Rushes at the player.
+    Charger,
+    /// Ignores gravity and chases the player through the air.
+    Flyer,
+}
+
+impl Archetype {
+    pub fn behavior(self) -> &'static dyn EnemyBehavior {
+        match self {
+            Archetype::Brute => &brute::Brute,
+            Archetype::Caster => &caster::Caster,
+            Archetype::Charger => &charger::Charger,
+            Archetype::Flyer => &flyer::Flyer,
+        }
+    }
+}
+
+/// Starting combat stats of an archetype.
+pub struct EnemyStats {
+    pub resources: CombatResources,
+    pub def: Defense,
+    pub off: Offense,
+}
+
+/// What sets an archetype apart. Everything else lives in `EnemyCore`, shared by all enemies.
+pub trait EnemyBehavior {
+    fn stats(&self) -> EnemyStats;
+
+    /// Called when an attack starts, and again when a chain attack's follow up comes out.
+    fn strike(&self, enemy: &mut EnemyCore);
+
+    /// Flyers skip floor and ledge checks, so they never fall.
+    fn flies(&self) -> bool {
+        false
+    }
+
+    /// Chases the player on both axes instead of only along the ground.
+    fn chases_vertically(&self) -> bool {
+        false
+    }
+
+    /// States the archetype never enters, so their clips aren't required.
+    fn unreachable(&self) -> &'static [State] {
+        if self.flies() {
+            &[State::Falling {}]
+        } else {
+            &[]
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::entities::enemies::{archetypes::Archetype, enemy_state_machine::State};
+
+    #[test]
+    fn test_only_flyers_skip_falling() {
+        for archetype in [
+            Archetype::Brute,
+            Archetype::Caster,
+            Archetype::Charger,
+            Archetype::Flyer,
+        ] {
+            let behavior = archetype.behavior();
+            assert_eq!(
+                behavior.unreachable().contains(&State::Falling {}),
+                behavior.flies(),
+                "{archetype:?} falls without a fall clip, or flies and requires one"
+            );
+        }
+        assert!(Archetype::Caster.behavior().flies());
+        assert!(!Archetype::Charger.behavior().flies());
+    }
+}
@@ -1,179 +1,64 @@
 use godot::{
     builtin::Vector2,
-    classes::{Area2D, CharacterBody2D, ICharacterBody2D},
-    obj::{Base, Gd, OnReady, WithBaseField},
+    classes::{CharacterBody2D, ICharacterBody2D},
+    obj::{Base, OnReady, WithBaseField},
     prelude::{GodotClass, godot_api},
 };
-use statig::prelude::StateMachine;
 
-use super::enemy_state_machine as esm;
-use crate::entities::{
-    enemies::{enemy_context as ctx, physics, time},
-    graphics::Graphics,
-    movements::Direction,
+use crate::{
+    entities::enemies::{
+        archetypes::Archetype,
+        enemy_core::{self, EnemyActor, EnemyCore},
+        physics::MovementStrategy,
+    },
+    utils::{collision_layers::CollisionLayers, sm_history::EntityHistory},
 };
 
-/// Basic enemy type with a base of type `CharacterBody2D`.
+/// Enemy with a base of type `CharacterBody2D`, for archetypes that collide with the world.
 #[derive(GodotClass)]
 #[class(base = CharacterBody2D, init)]
 pub struct EnemyBodyActor {
+    #[export]
+    archetype: Archetype,
     #[export]
     left_target: Vector2,
     #[export]
     right_target: Vector2,
 
     #[init(val = OnReady::manual())]
-    movement: OnReady<physics::Movement>,
-    #[init(val = OnReady::manual())]
-    sensors: OnReady<ctx::EnemySensors>,
-    #[init(val = OnReady::manual())]
-    timers: OnReady<time::Timers>,
-    #[init(val = OnReady::manual())]
-    sm: OnReady<esm::EnemySMType>,
-    #[init(val = OnReady::from_base_fn(|this|{ Graphics::new(this)}))]
-    graphics: OnReady<Graphics>,
+    core: OnReady<EnemyCore>,
     body: Base<CharacterBody2D>,
 }
 
 #[godot_api]
 impl ICharacterBody2D for EnemyBodyActor {
     fn ready(&mut self) {
+        self.base_mut()
+            .set_collision_mask_value(CollisionLayers::PlatformLedges as i32, true);
         let this = self.to_gd();
-        self.movement.init(physics::Movement::new(
-            self.base().get_global_position(),
-            physics::Speeds::new(150.0, 175.0),
+        self.core.init(EnemyCore::new(
+            self.archetype,
+            MovementStrategy::MoveAndSlide(this.clone().upcast()),
             self.left_target,
             self.right_target,
         ));
-        self.sensors
-            .init(ctx::EnemySensors::default_new(&self.to_gd().upcast()));
-        self.timers
-            .init(time::Timers::default_new(&self.to_gd().upcast()));
-        self.sm
-            .init(esm::EnemySMType::Basic(StateMachine::default()));
-
-        self.timers.connect_signals(
-            {
-                let mut this = this.clone();
-                move || this.bind_mut().on_attack_timeout()
-            },
-            {
-                let mut this = this.clone();
-                move || this.bind_mut().on_patrol_timeout()
-            },
-            {
-                let mut this = this.clone();
-                move || this.bind_mut().on_idle_timeout()
-            },
-            || (),
-            || (),
-        );
-
-        self.sensors.connect_signals(
-            |_| (),
-            |_| (),
-            |_| (),
-            |_| (),
-            {
-                let mut this = this.clone();
-                move |area| this.bind_mut().on_aggro_area_entered(area)
-            },
-            {
-                let mut this = this.clone();
-                move |area| this.bind_mut().on_aggro_area_exited(area)
-            },
-            {
-                let mut this = this.clone();
-                move |area| this.bind_mut().on_attack_area_entered(area)
-            },
-            |_| (),
-        );
-        self.timers.idle.start();
-
-        // self.sensors.hit_reg.hurtbox.bind_mut().data = Some(AttackData {
-        //     parryable: false,
-        //     damage: Damage {
-        //         raw: 10,
-        //         d_type: DamageType::Physical,
-        //     },
-        // });
+        enemy_core::connect_signals(&this, &mut self.core);
     }
 
     fn physics_process(&mut self, delta: f32) {
-        if !self.base().is_on_floor() {
-            self.sm.handle(&esm::EnemyEvent::FailedFloorCheck);
-        }
-        if let &esm::State::Falling {} = self.sm.state()
-            && self.base().is_on_floor()
-        {
-            self.sm.handle(&esm::EnemyEvent::OnFloor);
-        }
-
-        match self.sm.state() {
-            esm::State::Patrol {} | esm::State::ChasePlayer {}
-                if self.sensors.are_raycasts_failing() =>
-            {
-                match self.sensors.which() {
-                    ctx::Raycasts::Ground(dir) | ctx::Raycasts::Wall(dir) => {
-                        self.sm.handle(&esm::EnemyEvent::RayCastFailed(dir));
-                    }
-                }
-            }
-            esm::State::RecoverLeft {} | esm::State::RecoverRight {}
-                if !self.sensors.is_wall_cast_colliding() =>
-            {
-                self.sm.handle(&esm::EnemyEvent::WallCastRecovered);
-            }
-            _ => (),
-        }
-        let this = self.to_gd();
-        self.movement.update(
-            &mut physics::MovementStrategy::MoveAndSlide(this.upcast()),
-            self.sm.state(),
-            self.sensors.player_detection.player_position(),
-            delta,
-        );
-        self.graphics.update(
-            self.sm.state(),
-            Direction::from_vel(self.movement.velocity()),
-        );
+        self.core.physics_process(delta);
     }
 }
 
-impl EnemyBodyActor {
-    pub fn on_aggro_area_entered(&mut self, _area: Gd<Area2D>) {
-        self.sm.handle(&esm::EnemyEvent::FoundPlayer);
-    }
-
-    pub fn on_aggro_area_exited(&mut self, _area: Gd<Area2D>) {
-        self.sm.handle(&esm::EnemyEvent::LostPlayer);
-        self.timers.idle.start();
-    }
-
-    pub fn on_attack_area_entered(&mut self, _area: Gd<Area2D>) {
-        self.sm.handle(&esm::EnemyEvent::InAttackRange);
-    }
-
-    pub fn on_idle_timeout(&mut self) {
-        if self.sm.state() == (&esm::State::Idle {}) {
-            self.movement.patrol();
-
-            self.sm
-                .handle(&esm::EnemyEvent::TimerElapsed(time::EnemyTimers::Idle));
-            self.timers.patrol.start();
-        }
-    }
-
-    pub fn on_patrol_timeout(&mut self) {
-        if self.sm.state() == (&esm::State::Patrol {}) {
-            self.sm
-                .handle(&esm::EnemyEvent::TimerElapsed(time::EnemyTimers::Patrol));
-            self.timers.idle.start();
-        }
+impl EnemyActor for EnemyBodyActor {
+    fn core_mut(&mut self) -> &mut EnemyCore {
+        &mut self.core
     }
+}
 
-    fn on_attack_timeout(&mut self) {
-        self.sm
-            .handle(&esm::EnemyEvent::TimerElapsed(time::EnemyTimers::Attack));
+impl EnemyBodyActor {
+    /// The enemy's state and recent transitions, for the debug overlay.
+    pub fn sm_history(&self) -> EntityHistory {
+        self.core.sm_history()
     }
 }
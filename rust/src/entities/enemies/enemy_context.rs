@@ -1,6 +1,7 @@
 use crate::{
     entities::{hit_reg::HitReg, movements::Direction},
     utils::collision_layers::CollisionLayers,
+    world::moving_platform::MovingPlatform,
 };
 
 use godot::{
@@ -138,6 +139,14 @@ impl EnemySensors {
     /// Provides `Self` by obtaining the required nodes at the expected path in the `SceneTree`.
     /// "Expected path" meaning: `EnemySensors/*`
     pub fn default_new(base_enemy: &Gd<Node>) -> Self {
+        // Enemies can walk on one-way platforms, so the platform's edges count as ledges.
+        let mut left_ground_cast: Gd<RayCast2D> =
+            base_enemy.get_node_as("EnemySensors/LeftGroundCast");
+        let mut right_ground_cast: Gd<RayCast2D> =
+            base_enemy.get_node_as("EnemySensors/RightGroundCast");
+        left_ground_cast.set_collision_mask_value(CollisionLayers::PlatformLedges as i32, true);
+        right_ground_cast.set_collision_mask_value(CollisionLayers::PlatformLedges as i32, true);
+
         Self {
             hit_reg: HitReg::new(
                 base_enemy.get_node_as("EnemySensors/Hitbox"),
@@ -147,8 +156,8 @@ impl EnemySensors {
                 base_enemy.get_node_as("EnemySensors/AggroArea"),
                 base_enemy.get_node_as("EnemySensors/AttackArea"),
             ),
-            left_ground_cast: base_enemy.get_node_as("EnemySensors/LeftGroundCast"),
-            right_ground_cast: base_enemy.get_node_as("EnemySensors/RightGroundCast"),
+            left_ground_cast,
+            right_ground_cast,
             left_wall_cast: base_enemy.get_node_as("EnemySensors/LeftWallCast"),
             right_wall_cast: base_enemy.get_node_as("EnemySensors/RightWallCast"),
         }
@@ -219,9 +228,22 @@ impl EnemySensors {
         self.left_wall_cast.is_colliding() || self.right_wall_cast.is_colliding()
     }
 
+    /// Velocity of the `MovingPlatform` the entity stands on, if any.
+    pub fn platform_velocity(&self) -> Vector2 {
+        [&self.left_ground_cast, &self.right_ground_cast]
+            .into_iter()
+            .find_map(|cast| {
+                cast.get_collider()?
+                    .try_cast::<MovingPlatform>()
+                    .ok()
+                    .map(|platform| platform.bind().velocity())
+            })
+            .unwrap_or(Vector2::ZERO)
+    }
+
     /// Groundcasts should always be colliding unless the entity is at a ledge.
     fn is_groundcast_colliding(&self) -> bool {
-        self.left_ground_cast.is_colliding() || self.right_wall_cast.is_colliding()
+        self.left_ground_cast.is_colliding() && self.right_ground_cast.is_colliding()
     }
 
     pub fn wall_collision_dir(&self) -> Direction {
@@ -0,0 +1,337 @@
+use godot::{
+    builtin::Vector2,
+    classes::{Area2D, Node2D},
+    obj::{Gd, WithBaseField},
+};
+
+use crate::{
+    entities::{
+        combat::{
+            defense::Defense,
+            offense::{Attack, Offense, PlayerAttacks},
+            resources::CombatResources,
+        },
+        enemies::{
+            archetypes::{Archetype, EnemyBehavior, EnemyStats},
+            enemy_context::{EnemySensors, Raycasts},
+            enemy_state_machine::{EnemyEvent, EnemySMType, State},
+            physics::{Movement, MovementStrategy},
+            time::{EnemyTimers, Timers},
+        },
+        graphics::Graphics,
+        hit_reg::Hurtbox,
+        movement_tuning::MovementTuning,
+        movements::Direction,
+    },
+    utils::{
+        global_data_singleton::GlobalData,
+        sm_history::EntityHistory,
+        time_control::{self, BOSS_KILL_SLOW_MOTION, HITSTOP_FRAMES},
+    },
+    world::{
+        map::BOSS_GROUP,
+        world_state::{ObjectState, record_node_state},
+    },
+};
+
+/// An enemy class built around an `EnemyCore`.
+pub trait EnemyActor: WithBaseField {
+    fn core_mut(&mut self) -> &mut EnemyCore;
+}
+
+/// Everything enemies share: sensors, timers, state machine, combat and movement. What differs
+/// between archetypes is left to their `EnemyBehavior`.
+///
+/// Expects the enemy's scene to have `Sprite2D`, `AnimationPlayer`, the timers of `Timers` and
+/// `EnemySensors/*`.
+pub struct EnemyCore {
+    archetype: Archetype,
+    strategy: MovementStrategy,
+    pub movement: Movement,
+    pub sensors: EnemySensors,
+    pub timers: Timers,
+    pub sm: EnemySMType,
+    graphics: Graphics,
+    pub resources: CombatResources,
+    def: Defense,
+    off: Offense,
+}
+
+impl EnemyCore {
+    pub fn new(
+        archetype: Archetype,
+        strategy: MovementStrategy,
+        left_target: Vector2,
+        right_target: Vector2,
+    ) -> Self {
+        let node = strategy.node();
+        let behavior = archetype.behavior();
+        let tuning = MovementTuning::load_or_default();
+        let mut movement = Movement::new(
+            node.get_global_position(),
+            tuning.bind().enemy_speeds(),
+            tuning.bind().enemy_gravity,
+            left_target,
+            right_target,
+        );
+        movement.set_chases_vertically(behavior.chases_vertically());
+
+        let mut graphics = Graphics::new(&node.clone().upcast());
+        let _ = graphics.validate(
+            &format!("{} ({archetype:?})", node.get_name()),
+            behavior.unreachable(),
+        );
+        let EnemyStats {
+            resources,
+            def,
+            off,
+        } = behavior.stats();
+
+        Self {
+            archetype,
+            sensors: EnemySensors::default_new(&node.clone().upcast()),
+            timers: Timers::default_new(&node.upcast()),
+            strategy,
+            movement,
+            sm: EnemySMType::basic(),
+            graphics,
+            resources,
+            def,
+            off,
+        }
+    }
+
+    fn behavior(&self) -> &'static dyn EnemyBehavior {
+        self.archetype.behavior()
+    }
+
+    pub fn node(&self) -> Gd<Node2D> {
+        self.strategy.node()
+    }
+
+    /// The enemy's state and recent transitions, for the debug overlay.
+    pub fn sm_history(&self) -> EntityHistory {
+        EntityHistory {
+            name: self.node().get_name().to_string(),
+            state: format!("{:?}", self.sm.state()),
+            history: self.sm.history().clone(),
+        }
+    }
+
+    pub fn physics_process(&mut self, delta: f32) {
+        if time_control::is_frozen(&self.node().upcast()) {
+            return;
+        }
+        let _ = self.resources.tick_resources(delta);
+        self.check_ground();
+        self.drive_attacks();
+
+        if let MovementStrategy::ManualSetPosition(_) = self.strategy {
+            self.movement.set_carried(self.sensors.platform_velocity());
+        }
+        self.movement.update(
+            &mut self.strategy,
+            self.sm.state(),
+            self.sensors.player_detection.player_position(),
+            delta,
+        );
+        self.graphics.update(
+            self.sm.state(),
+            Direction::from_vel(self.movement.velocity()),
+        );
+    }
+
+    /// Floor, ledge and wall checks, for enemies that walk.
+    fn check_ground(&mut self) {
+        let MovementStrategy::MoveAndSlide(body) = &self.strategy else {
+            return;
+        };
+        if self.behavior().flies() {
+            return;
+        }
+        let on_floor = body.is_on_floor();
+        if !on_floor {
+            self.sm.handle(&EnemyEvent::FailedFloorCheck);
+        }
+        if let &State::Falling {} = self.sm.state()
+            && on_floor
+        {
+            self.sm.handle(&EnemyEvent::OnFloor);
+        }
+
+        match self.sm.state() {
+            State::Patrol {} | State::ChasePlayer {} if self.sensors.are_raycasts_failing() => {
+                match self.sensors.which() {
+                    Raycasts::Ground(dir) | Raycasts::Wall(dir) => {
+                        self.sm.handle(&EnemyEvent::RayCastFailed(dir));
+                    }
+                }
+            }
+            State::RecoverLeft {} | State::RecoverRight {}
+                if !self.sensors.is_wall_cast_colliding() =>
+            {
+                self.sm.handle(&EnemyEvent::WallCastRecovered);
+            }
+            _ => (),
+        }
+    }
+
+    /// Starts attacks once the attack cooldown is over.
+    fn drive_attacks(&mut self) {
+        if self.timers.attack.get_time_left() != 0.0 {
+            return;
+        }
+        match self.sm.state() {
+            State::ChasePlayer {} if self.sensors.player_detection.attack_area_overlapping() => {
+                self.sm.handle(&EnemyEvent::InAttackRange);
+            }
+            State::Attack {} => {
+                self.behavior().strike(self);
+                self.timers.attack.start();
+                self.timers.attack_anim.start();
+            }
+            State::Attack2 {} => {
+                self.behavior().strike(self);
+                self.timers.attack_chain.start();
+                self.timers.attack.start();
+            }
+            _ => (),
+        }
+    }
+
+    /// Builds `attack` with the enemy's buffs, failing if the enemy can't pay for it.
+    pub fn prepare_attack(&mut self, attack: PlayerAttacks) -> Result<Attack, ()> {
+        let mut attack = Offense::try_attack(attack, &mut self.resources, 1)?;
+        self.off.apply_buffs(&mut attack);
+        Ok(attack)
+    }
+
+    /// Sets the attack dealt by the enemy's own hurtbox, for archetypes that hit in melee.
+    pub fn arm_hurtbox(&mut self, attack: PlayerAttacks) {
+        if let Ok(attack) = self.prepare_attack(attack) {
+            self.sensors.hit_reg.hurtbox.bind_mut().set_attack(attack);
+        }
+    }
+
+    fn on_tuning_reloaded(&mut self, tuning: Gd<MovementTuning>) {
+        self.movement.set_tuning(&tuning.bind());
+    }
+
+    fn on_hitbox_entered(&mut self, area: Gd<Area2D>) {
+        let hurtbox = area.cast::<Hurtbox>();
+
+        // BUG: Seems like on occasion the hurtbox sets the attack after the hitbox checks it,
+        // leading to a panic upon unwrapping.
+        // Could try:
+        // - disabling multi-threading in Godot editor
+        // - deferring the hitbox check to the next idle frame
+        let attack = hurtbox.bind().attack.clone().unwrap();
+
+        let damage = self.def.apply_resistances(&attack);
+        self.resources.take_damage(damage);
+        let mut node = self.node();
+        let mut frozen = vec![node.clone().upcast()];
+        frozen.extend(hurtbox.get_owner());
+        time_control::hitstop(&frozen, HITSTOP_FRAMES);
+        if self.resources.health().is_dead() {
+            self.sm.handle(&EnemyEvent::Death);
+            if node.is_in_group(BOSS_GROUP) {
+                record_node_state(&node, ObjectState::BossDefeated);
+                time_control::slow_motion(BOSS_KILL_SLOW_MOTION);
+            }
+            node.run_deferred_gd(|mut node| node.queue_free());
+        }
+    }
+
+    fn on_aggro_area_entered(&mut self, _area: Gd<Area2D>) {
+        self.sm.handle(&EnemyEvent::FoundPlayer);
+    }
+
+    fn on_aggro_area_exited(&mut self, _area: Gd<Area2D>) {
+        self.sm.handle(&EnemyEvent::LostPlayer);
+        self.timers.idle.start();
+    }
+
+    fn on_attack_area_entered(&mut self, _area: Gd<Area2D>) {
+        self.sm.handle(&EnemyEvent::InAttackRange);
+    }
+
+    fn on_idle_timeout(&mut self) {
+        if self.sm.state() == (&State::Idle {}) {
+            self.sm.handle(&EnemyEvent::TimerElapsed(EnemyTimers::Idle));
+            self.timers.patrol.start();
+            self.movement.patrol();
+        }
+    }
+
+    fn on_patrol_timeout(&mut self) {
+        if self.sm.state() == (&State::Patrol {}) {
+            self.sm
+                .handle(&EnemyEvent::TimerElapsed(EnemyTimers::Patrol));
+            self.timers.idle.start();
+        }
+    }
+
+    fn on_attack_timeout(&mut self) {
+        self.sm
+            .handle(&EnemyEvent::TimerElapsed(EnemyTimers::Attack));
+    }
+
+    fn on_attack_chain_timeout(&mut self) {
+        if self.sm.state() == (&State::Attack2 {}) {
+            self.behavior().strike(self);
+        }
+        self.sm
+            .handle(&EnemyEvent::TimerElapsed(EnemyTimers::AttackChain));
+    }
+
+    fn on_attack_anim_timeout(&mut self) {
+        self.sm
+            .handle(&EnemyEvent::TimerElapsed(EnemyTimers::AttackAnimation));
+    }
+}
+
+/// Connects the core's timers, sensors and tuning reloads to `enemy`, then starts idling.
+pub fn connect_signals<T: EnemyActor>(enemy: &Gd<T>, core: &mut EnemyCore) {
+    fn forward<T: EnemyActor>(
+        enemy: &Gd<T>,
+        callback: fn(&mut EnemyCore),
+    ) -> impl FnMut() + 'static {
+        let mut enemy = enemy.clone();
+        move || callback(enemy.bind_mut().core_mut())
+    }
+    fn forward_area<T: EnemyActor>(
+        enemy: &Gd<T>,
+        callback: fn(&mut EnemyCore, Gd<Area2D>),
+    ) -> impl FnMut(Gd<Area2D>) + 'static {
+        let mut enemy = enemy.clone();
+        move |area| callback(enemy.bind_mut().core_mut(), area)
+    }
+
+    core.timers.connect_signals(
+        forward(enemy, EnemyCore::on_attack_timeout),
+        forward(enemy, EnemyCore::on_patrol_timeout),
+        forward(enemy, EnemyCore::on_idle_timeout),
+        forward(enemy, EnemyCore::on_attack_chain_timeout),
+        forward(enemy, EnemyCore::on_attack_anim_timeout),
+    );
+    core.sensors.connect_signals(
+        forward_area(enemy, EnemyCore::on_hitbox_entered),
+        |_| (),
+        |_| (),
+        |_| (),
+        forward_area(enemy, EnemyCore::on_aggro_area_entered),
+        forward_area(enemy, EnemyCore::on_aggro_area_exited),
+        forward_area(enemy, EnemyCore::on_attack_area_entered),
+        |_| (),
+    );
+    GlobalData::singleton()
+        .bind_mut()
+        .sig_handler()
+        .movement_tuning_reloaded()
+        .builder()
+        .connect_other_mut(enemy, |this: &mut T, tuning: Gd<MovementTuning>| {
+            this.core_mut().on_tuning_reloaded(tuning)
+        });
+    core.timers.idle.start();
+}
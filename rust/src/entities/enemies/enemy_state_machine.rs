@@ -1,28 +1,45 @@
-use std::fmt::Display;
-
+use godot::{classes::Engine, obj::Singleton};
 use statig::Response::Handled;
 use statig::{Response, state_machine};
 
 use crate::entities::enemies::time::EnemyTimers;
+use crate::entities::graphics::{Clip, StateAnimations};
 use crate::entities::movements::Direction;
+use crate::utils::sm_history::TransitionHistory;
 
 #[derive(Clone)]
 pub enum EnemySMType {
-    Basic(statig::blocking::StateMachine<EnemyStateMachine>),
+    Basic(
+        statig::blocking::StateMachine<EnemyStateMachine>,
+        TransitionHistory,
+    ),
 }
 
 impl EnemySMType {
+    pub fn basic() -> Self {
+        EnemySMType::Basic(Default::default(), TransitionHistory::default())
+    }
+
     pub fn handle(&mut self, event: &EnemyEvent) {
         match self {
-            EnemySMType::Basic(state_machine) => {
+            EnemySMType::Basic(state_machine, history) => {
+                let from = state_machine.state().clone();
                 state_machine.handle(event);
+                let frame = Engine::singleton().get_physics_frames();
+                history.record(frame, &from, event, state_machine.state());
             }
         }
     }
 
+    pub fn history(&self) -> &TransitionHistory {
+        match self {
+            EnemySMType::Basic(_, history) => history,
+        }
+    }
+
     pub fn state(&self) -> &State {
         match self {
-            EnemySMType::Basic(state_machine) => state_machine.state(),
+            EnemySMType::Basic(state_machine, _) => state_machine.state(),
         }
     }
 }
@@ -47,20 +64,19 @@ pub enum EnemyEvent {
     None,
 }
 
-impl Display for State {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            State::Patrol { .. } | State::RecoverLeft {} | State::RecoverRight {} => {
-                write!(f, "patrol")
-            }
-            State::Idle {} => write!(f, "idle"),
-            State::ChasePlayer { .. } => write!(f, "patrol"),
-            State::Attack { .. } => write!(f, "attack"),
-            State::Attack2 { .. } => write!(f, "chain_attack"),
-            State::Falling { .. } => write!(f, "fall"),
-            State::Dead {} => write!(f, "dead"),
-        }
-    }
+impl StateAnimations for State {
+    const ANIMATIONS: &'static [(Self, Clip)] = &[
+        (State::Idle {}, Clip::Directional("idle")),
+        (State::Patrol {}, Clip::Directional("patrol")),
+        (State::RecoverLeft {}, Clip::Directional("patrol")),
+        (State::RecoverRight {}, Clip::Directional("patrol")),
+        (State::ChasePlayer {}, Clip::Directional("patrol")),
+        (State::Attack {}, Clip::Directional("attack")),
+        (State::Attack2 {}, Clip::Directional("chain_attack")),
+        (State::Falling {}, Clip::Directional("fall")),
+        // There is no death asset yet.
+        (State::Dead {}, Clip::Keep),
+    ];
 }
 
 #[state_machine(initial = "State::idle()", state(derive(Debug, Clone, PartialEq)))]
@@ -1,8 +1,10 @@
+pub mod archetypes;
 mod bouncing_enemy;
 pub mod enemy_body_actor;
 mod enemy_context;
-mod enemy_state_machine;
-mod physics;
+pub mod enemy_core;
+pub mod enemy_state_machine;
+pub mod physics;
 pub mod projectile;
 pub mod projectile_enemy;
 mod time;
@@ -4,7 +4,7 @@ use godot::{
     obj::Gd,
 };
 
-use crate::entities::enemies::enemy_state_machine::State;
+use crate::entities::{enemies::enemy_state_machine::State, movement_tuning::MovementTuning};
 
 #[derive(Clone, Copy)]
 pub struct Speeds {
@@ -22,30 +22,61 @@ impl Speeds {
 pub struct Movement {
     current_position: Vector2,
     speeds: Speeds,
+    gravity: f32,
     velocity: Vector2,
     left_target: Vector2,
     right_target: Vector2,
+    /// Chases on both axes instead of only along the ground.
+    chases_vertically: bool,
+    /// Velocity of a charge in progress, held until the attack ends.
+    charge: Option<Vector2>,
+    /// Velocity of the platform carrying the entity. Only used by `ManualSetPosition`, as
+    /// `move_and_slide` already rides platforms.
+    carried: Vector2,
 }
 
 impl Movement {
     pub fn new(
         current_position: Vector2,
         speeds: Speeds,
+        gravity: f32,
         left_target: Vector2,
         right_target: Vector2,
     ) -> Self {
         Self {
             current_position,
             speeds,
+            gravity,
             velocity: Vector2::ZERO,
             left_target,
             right_target,
+            chases_vertically: false,
+            charge: None,
+            carried: Vector2::ZERO,
         }
     }
 
     fn apply_gravity(&mut self, delta: f32) {
-        const GRAVITY: f32 = 1500.0;
-        self.velocity.y += GRAVITY * delta;
+        self.velocity.y += self.gravity * delta;
+    }
+
+    /// Applies the values of a `MovementTuning` resource.
+    pub fn set_tuning(&mut self, tuning: &MovementTuning) {
+        self.speeds = tuning.enemy_speeds();
+        self.gravity = tuning.enemy_gravity;
+    }
+
+    pub fn set_chases_vertically(&mut self, chases_vertically: bool) {
+        self.chases_vertically = chases_vertically;
+    }
+
+    /// Moves at `velocity` until the current attack ends.
+    pub fn charge(&mut self, velocity: Vector2) {
+        self.charge = Some(velocity);
+    }
+
+    pub fn set_carried(&mut self, velocity: Vector2) {
+        self.carried = velocity;
     }
 
     pub fn velocity(&self) -> Vector2 {
@@ -70,10 +101,28 @@ impl Movement {
         player_pos: Option<Vector2>,
         delta: f32,
     ) {
+        self.steer(state, player_pos, delta);
+        self.apply_movement(strategy, delta);
+    }
+
+    /// Sets the velocity for `state`, without moving.
+    fn steer(&mut self, state: &State, player_pos: Option<Vector2>, delta: f32) {
+        if !matches!(state, State::Attack {} | State::Attack2 {}) {
+            self.charge = None;
+        }
         match state {
             State::RecoverLeft {} => self.velocity.x = Vector2::RIGHT.x,
             State::RecoverRight {} => self.velocity.x = Vector2::LEFT.x,
             State::Patrol {} | State::Falling {} => (),
+            State::ChasePlayer {} if self.chases_vertically => {
+                if let Some(player_pos) = player_pos
+                    && self.current_position.distance_to(player_pos) >= 30.0
+                {
+                    self.velocity = self.current_position.direction_to(player_pos);
+                } else {
+                    self.velocity = Vector2::ZERO;
+                }
+            }
             State::ChasePlayer {} => {
                 if let Some(player_pos) = player_pos
                     && (self.current_position.x - player_pos.x).abs() >= 30.0
@@ -83,10 +132,13 @@ impl Movement {
                     self.velocity = Vector2::ZERO;
                 }
             }
+            State::Attack {} | State::Attack2 {} if let Some(charge) = self.charge => {
+                self.velocity = charge;
+                return;
+            }
             _ => self.velocity = Vector2::ZERO,
         }
         self.accelerate(state, delta);
-        self.apply_movement(strategy, delta);
     }
 
     fn accelerate(&mut self, state: &State, delta: f32) {
@@ -112,7 +164,9 @@ impl Movement {
             }
             MovementStrategy::ManualSetPosition(node) => {
                 self.current_position = node.get_global_position();
-                node.set_global_position(self.current_position + self.velocity * delta);
+                node.set_global_position(
+                    self.current_position + (self.velocity + self.carried) * delta,
+                );
             }
         }
     }
@@ -125,3 +179,96 @@ pub enum MovementStrategy {
     MoveAndSlide(Gd<CharacterBody2D>),
     ManualSetPosition(Gd<Node2D>),
 }
+
+impl MovementStrategy {
+    pub fn node(&self) -> Gd<Node2D> {
+        match self {
+            MovementStrategy::MoveAndSlide(body) => body.clone().upcast(),
+            MovementStrategy::ManualSetPosition(node) => node.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use godot::builtin::Vector2;
+
+    use crate::entities::enemies::{
+        archetypes::Archetype,
+        enemy_state_machine::State,
+        physics::{Movement, Speeds},
+    };
+
+    const DELTA: f32 = 1.0 / 60.0;
+
+    fn movement() -> Movement {
+        Movement::new(
+            Vector2::ZERO,
+            Speeds::new(100.0, 200.0),
+            1500.0,
+            Vector2::new(-100.0, 0.0),
+            Vector2::new(100.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn test_charge_lasts_for_the_attack() {
+        let mut movement = movement();
+        let charge = Vector2::RIGHT * 450.0;
+        movement.charge(charge);
+
+        movement.steer(&State::Attack {}, None, DELTA);
+        assert_eq!(movement.velocity(), charge);
+        movement.steer(&State::Attack2 {}, None, DELTA);
+        assert_eq!(
+            movement.velocity(),
+            charge,
+            "charge ended during a chain attack"
+        );
+
+        movement.steer(&State::ChasePlayer {}, None, DELTA);
+        assert_eq!(movement.velocity(), Vector2::ZERO);
+        movement.steer(&State::Attack {}, None, DELTA);
+        assert_eq!(
+            movement.velocity(),
+            Vector2::ZERO,
+            "charge outlived its attack"
+        );
+    }
+
+    #[test]
+    fn test_only_flyers_chase_vertically() {
+        let player_pos = Some(Vector2::new(0.0, -100.0));
+        let mut walker = movement();
+        walker.steer(&State::ChasePlayer {}, player_pos, DELTA);
+        assert_eq!(walker.velocity(), Vector2::ZERO);
+
+        let mut flyer = movement();
+        flyer.set_chases_vertically(Archetype::Flyer.behavior().chases_vertically());
+        flyer.steer(&State::ChasePlayer {}, player_pos, DELTA);
+        assert_eq!(flyer.velocity(), Vector2::UP * 200.0);
+    }
+
+    #[test]
+    fn test_casters_keep_their_height() {
+        let mut caster = movement();
+        caster.set_chases_vertically(Archetype::Caster.behavior().chases_vertically());
+        caster.steer(
+            &State::ChasePlayer {},
+            Some(Vector2::new(0.0, 100.0)),
+            DELTA,
+        );
+        assert_eq!(
+            caster.velocity(),
+            Vector2::ZERO,
+            "caster dove at the player"
+        );
+
+        caster.steer(
+            &State::ChasePlayer {},
+            Some(Vector2::new(-100.0, 100.0)),
+            DELTA,
+        );
+        assert_eq!(caster.velocity(), Vector2::LEFT * 200.0);
+    }
+}
@@ -44,6 +44,22 @@ pub enum Stat {
     Level,
 }
 
+impl Stat {
+    /// Parses a snake_case stat name, e.g. `max_health`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "health" => Some(Stat::Health),
+            "max_health" => Some(Stat::MaxHealth),
+            "heal_amount" => Some(Stat::HealAmount),
+            "energy" => Some(Stat::Energy),
+            "mana" => Some(Stat::Mana),
+            "attack_damage" => Some(Stat::AttackDamage),
+            "level" => Some(Stat::Level),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 // The optional second tuple value is used for "caching" the value when a modification is applied.
 // When the modification is removed, the values are swapped and the second value is 'Option::None`.
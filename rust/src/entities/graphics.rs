@@ -1,17 +1,76 @@
 use godot::{
     classes::{AnimationPlayer, Node, Sprite2D},
+    global::godot_error,
     obj::Gd,
 };
-use statig::{IntoStateMachine, blocking::State};
-use std::fmt::Display;
 
 use crate::entities::movements::Direction;
 
+/// Plays one-off animations for the player's state machine, so it can run against fakes in tests.
+pub trait AnimationSink {
+    /// Plays the directional `clip` once, then resumes the current animation. The clip should be
+    /// in the owner's `StateAnimations`, so `Graphics::validate` checks it.
+    fn play_once(&mut self, clip: &'static str, dir: Direction);
+}
+
+/// What a state plays while it's active.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Clip {
+    /// Plays `{name}_{direction}`, e.g. `run_left`.
+    Directional(&'static str),
+    /// Keeps the current animation playing, for states without an animation of their own.
+    Keep,
+}
+
+/// Maps the states of a state machine to their animations.
+pub trait StateAnimations: PartialEq + Sized + 'static {
+    /// Every state and the clip it plays. States missing from the table keep the current
+    /// animation.
+    const ANIMATIONS: &'static [(Self, Clip)];
+    /// Directional clips played outside of the table, e.g. by the state machine itself.
+    const EXTRA_CLIPS: &'static [&'static str] = &[];
+    /// Played in place of clips missing from the `AnimationPlayer`.
+    const FALLBACK: &'static str = "idle";
+
+    fn clip(&self) -> Clip {
+        Self::ANIMATIONS
+            .iter()
+            .find(|(state, _)| state == self)
+            .map_or(Clip::Keep, |(_, clip)| *clip)
+    }
+
+    /// Every animation name the table and extra clips can play, apart from the clips of
+    /// `unreachable` states.
+    fn required_clips(unreachable: &[Self]) -> Vec<String> {
+        let names = Self::ANIMATIONS
+            .iter()
+            .filter(|(state, _)| !unreachable.contains(state))
+            .filter_map(|(_, clip)| match clip {
+                Clip::Directional(name) => Some(*name),
+                Clip::Keep => None,
+            })
+            .chain(Self::EXTRA_CLIPS.iter().copied());
+        let mut clips = Vec::new();
+        for name in names {
+            for dir in [Direction::Right, Direction::Left] {
+                let clip = format!("{name}_{dir}");
+                if !clips.contains(&clip) {
+                    clips.push(clip);
+                }
+            }
+        }
+        clips
+    }
+}
+
 #[derive(Clone)]
 pub struct Graphics {
     sprite: Gd<Sprite2D>,
     pub animation_player: Gd<AnimationPlayer>,
     previous_animation: String,
+    /// Clips found missing by `validate`, which play `fallback` instead.
+    missing: Vec<String>,
+    fallback: &'static str,
 }
 
 impl Graphics {
@@ -20,20 +79,37 @@ impl Graphics {
             sprite: node.get_node_as::<Sprite2D>("Sprite2D"),
             animation_player: node.get_node_as::<AnimationPlayer>("AnimationPlayer"),
             previous_animation: String::new(),
+            missing: Vec::new(),
+            fallback: "idle",
         }
     }
 
-    pub fn update<T: State<impl IntoStateMachine> + Display + PartialEq>(
+    /// Checks that the `AnimationPlayer` has every clip `T` can play, logging the missing ones.
+    /// Missing clips play `T::FALLBACK` instead. `unreachable` lists states the owner never
+    /// enters.
+    pub fn validate<T: StateAnimations>(
         &mut self,
-        state: &T,
-        dir: Direction,
-    ) {
-        // Do not have an animation for casting a spell.
-        if state.to_string() == "cast_spell" {
-            // TODO: Tween player sprite or add shader for casting spells.
-            return;
+        owner: &str,
+        unreachable: &[T],
+    ) -> Result<(), ()> {
+        self.fallback = T::FALLBACK;
+        self.missing = T::required_clips(unreachable)
+            .into_iter()
+            .filter(|clip| !self.animation_player.has_animation(clip))
+            .collect();
+        if self.missing.is_empty() {
+            Ok(())
+        } else {
+            godot_error!("{owner} is missing animations: {}", self.missing.join(", "));
+            Err(())
         }
-        let anim = format!("{state}_{dir}");
+    }
+
+    pub fn update<T: StateAnimations>(&mut self, state: &T, dir: Direction) {
+        let Clip::Directional(name) = state.clip() else {
+            return;
+        };
+        let anim = self.resolve(name, dir);
         if anim != self.previous_animation {
             self.animation_player.play_ex().name(&anim).done();
             self.previous_animation = anim;
@@ -47,10 +123,68 @@ impl Graphics {
         self.animation_player.queue(&cur);
     }
 
-    pub fn get_animation_length(&self, name: &str) -> f64 {
-        let Some(anim) = self.animation_player.get_animation(name) else {
-            return 0.0;
-        };
-        f64::from(anim.get_length())
+    /// The animation played for the directional clip `name`, or the fallback's if it's missing.
+    fn resolve(&self, name: &str, dir: Direction) -> String {
+        let anim = format!("{name}_{dir}");
+        if self.missing.contains(&anim) {
+            format!("{}_{dir}", self.fallback)
+        } else {
+            anim
+        }
+    }
+
+    /// The length of the clip in seconds, if the `AnimationPlayer` has it.
+    pub fn get_animation_length(&self, name: &str) -> Option<f64> {
+        let anim = self.animation_player.get_animation(name)?;
+        Some(f64::from(anim.get_length()))
+    }
+}
+
+impl AnimationSink for Graphics {
+    fn play_once(&mut self, clip: &'static str, dir: Direction) {
+        let anim = self.resolve(clip, dir);
+        self.play_then_resume(&anim);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::entities::{
+        enemies::enemy_state_machine as esm,
+        graphics::{Clip, StateAnimations},
+        player::character_state_machine as csm,
+    };
+
+    /// Clips required by `T` that the scene's animation library doesn't have.
+    fn missing_from<T: StateAnimations>(scene: &str, unreachable: &[T]) -> Vec<String> {
+        T::required_clips(unreachable)
+            .into_iter()
+            .filter(|clip| !scene.contains(&format!("\"{clip}\": SubResource")))
+            .collect()
+    }
+
+    #[test]
+    fn test_scenes_have_every_mapped_animation() {
+        let player = include_str!("../../../godot/entities/player/main_character.tscn");
+        assert_eq!(
+            missing_from::<csm::State>(player, &[]),
+            Vec::<String>::new()
+        );
+
+        let enemy = include_str!("../../../godot/entities/enemies/test_enemy.tscn");
+        assert_eq!(missing_from::<esm::State>(enemy, &[]), Vec::<String>::new());
+
+        let projectile = include_str!("../../../godot/entities/enemies/projectile_enemy.tscn");
+        assert_eq!(
+            missing_from(projectile, &[esm::State::Falling {}]),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_unmapped_states_keep_playing() {
+        assert_eq!(csm::State::CastSpell {}.clip(), Clip::Keep);
+        assert_eq!(esm::State::Dead {}.clip(), Clip::Keep);
+        assert_eq!(csm::State::Swimming {}.clip(), Clip::Directional("falling"));
     }
 }
@@ -1,5 +1,5 @@
 use godot::{
-    classes::{Area2D, IArea2D},
+    classes::{Area2D, CollisionShape2D, IArea2D},
     meta::ToGodot,
     obj::{Base, Gd, WithBaseField},
     prelude::{GodotClass, godot_api},
@@ -39,6 +39,32 @@ impl Hurtbox {
     pub fn set_attack(&mut self, attack: Attack) {
         self.attack.replace(attack);
     }
+
+    /// Enables or disables all of the hurtbox's collision shapes.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        for child in self.base().get_children().iter_shared() {
+            if let Ok(mut shape) = child.try_cast::<CollisionShape2D>() {
+                shape.set_deferred("disabled", &(!enabled).to_variant());
+            }
+        }
+    }
+}
+
+/// The hurtbox an attack is dealt through, as seen by the player's state machine, so it can run
+/// against fakes in tests.
+pub trait AttackSlot {
+    fn set_attack(&mut self, attack: Attack);
+    fn set_enabled(&mut self, enabled: bool);
+}
+
+impl AttackSlot for Gd<Hurtbox> {
+    fn set_attack(&mut self, attack: Attack) {
+        self.bind_mut().set_attack(attack);
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.bind_mut().set_enabled(enabled);
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -1,7 +1,8 @@
 pub mod combat;
-mod enemies;
+pub mod enemies;
 pub mod entity_stats;
 pub mod graphics;
 pub mod hit_reg;
+pub mod movement_tuning;
 pub mod movements;
 pub mod player;
@@ -0,0 +1,165 @@
+use godot::{
+    classes::{IResource, Resource, ResourceLoader, resource_loader::CacheMode},
+    global::godot_warn,
+    prelude::*,
+};
+
+use crate::{
+    entities::{enemies::physics as enemy_physics, player::physics as player_physics},
+    utils::global_data_singleton::GlobalData,
+};
+
+pub const MOVEMENT_TUNING_PATH: &str = "res://resources/movement_tuning.tres";
+
+/// Designer facing movement values for the player and enemies.
+/// Reloaded at runtime in debug builds with the "dev_reload_tuning" action.
+#[derive(GodotClass)]
+#[class(init, base = Resource)]
+pub struct MovementTuning {
+    #[export_group(name = "Player")]
+    #[export]
+    #[init(val = 180.0)]
+    pub run_speed: f32,
+    #[export]
+    #[init(val = 450.0)]
+    pub jump_speed: f32,
+    #[export]
+    #[init(val = 800.0)]
+    pub dodge_speed: f32,
+    #[export]
+    #[init(val = 120.0)]
+    pub climb_speed: f32,
+    #[export]
+    #[init(val = 360.0)]
+    pub slide_speed: f32,
+    /// Fraction of `jump_speed` used when bouncing off of a down strike.
+    #[export]
+    #[init(val = 0.8)]
+    pub pogo_bounce_scale: f32,
+    #[export]
+    #[init(val = 900.0)]
+    pub gravity: f32,
+    #[export]
+    #[init(val = 1300.0)]
+    pub terminal_velocity: f32,
+
+    /// Jumps held longer than this, in seconds, fall at `held_jump_fall_speed` when released.
+    #[export_group(name = "Early Gravity")]
+    #[export]
+    #[init(val = 0.5)]
+    pub held_jump_time: f32,
+    /// Jumps released between `min_early_release` and `held_jump_time` fall at
+    /// `early_release_fall_speed`. All other releases fall at `short_jump_fall_speed`.
+    #[export]
+    #[init(val = 0.08)]
+    pub min_early_release: f32,
+    #[export]
+    #[init(val = 300.0)]
+    pub held_jump_fall_speed: f32,
+    #[export]
+    #[init(val = 350.0)]
+    pub early_release_fall_speed: f32,
+    #[export]
+    #[init(val = 450.0)]
+    pub short_jump_fall_speed: f32,
+
+    /// Falls at least this high, in pixels, cause a hard landing.
+    #[export_group(name = "Landing")]
+    #[export]
+    #[init(val = 256.0)]
+    pub hard_landing_height: f32,
+    /// Falls at least this high deal `fall_damage`, plus `fall_damage` for every
+    /// `fall_damage_step` pixels past it.
+    #[export]
+    #[init(val = 480.0)]
+    pub fall_damage_height: f32,
+    #[export]
+    #[init(val = 5)]
+    pub fall_damage: i64,
+    /// A step of 0 deals `fall_damage` once, however far the fall.
+    #[export]
+    #[init(val = 64.0)]
+    pub fall_damage_step: f32,
+
+    #[export_group(name = "Enemies")]
+    #[export]
+    #[init(val = 150.0)]
+    pub enemy_patrol_speed: f32,
+    #[export]
+    #[init(val = 175.0)]
+    pub enemy_aggro_speed: f32,
+    #[export]
+    #[init(val = 1500.0)]
+    pub enemy_gravity: f32,
+
+    base: Base<Resource>,
+}
+
+#[godot_api]
+impl IResource for MovementTuning {}
+
+impl MovementTuning {
+    /// Loads the tuning resource, falling back to the default values if it is missing.
+    pub fn load_or_default() -> Gd<Self> {
+        try_load::<Self>(MOVEMENT_TUNING_PATH).unwrap_or_else(|e| {
+            godot_warn!("Failed to load movement tuning, using defaults: {e}");
+            Self::new_gd()
+        })
+    }
+
+    /// Reloads the tuning resource from disk and notifies entities through the `GlobalData`
+    /// signal handler.
+    pub fn reload() {
+        let tuning = ResourceLoader::singleton()
+            .load_ex(MOVEMENT_TUNING_PATH)
+            .cache_mode(CacheMode::REPLACE)
+            .done()
+            .and_then(|r| r.try_cast::<Self>().ok());
+        match tuning {
+            // Deferred, as this is called while the player is bound.
+            Some(tuning) => {
+                let mut sigs = GlobalData::singleton().bind().sigs.clone();
+                sigs.run_deferred_gd(move |sigs| {
+                    sigs.signals().movement_tuning_reloaded().emit(&tuning);
+                });
+            }
+            None => godot_warn!("Failed to reload movement tuning at {MOVEMENT_TUNING_PATH}"),
+        }
+    }
+
+    pub fn player_speeds(&self) -> player_physics::Speeds {
+        player_physics::Speeds {
+            running: self.run_speed,
+            jumping: self.jump_speed,
+            dodging: self.dodge_speed,
+            climbing: self.climb_speed,
+            sliding: self.slide_speed,
+            pogo_bounce_scale: self.pogo_bounce_scale,
+        }
+    }
+
+    pub fn player_gravity(&self) -> player_physics::Gravity {
+        player_physics::Gravity {
+            gravity: self.gravity,
+            terminal_velocity: self.terminal_velocity,
+            held_jump_time: self.held_jump_time,
+            min_early_release: self.min_early_release,
+            held_jump_fall_speed: self.held_jump_fall_speed,
+            early_release_fall_speed: self.early_release_fall_speed,
+            short_jump_fall_speed: self.short_jump_fall_speed,
+        }
+    }
+
+    pub fn player_landing(&self) -> player_physics::LandingThresholds {
+        player_physics::LandingThresholds {
+            hard_landing_height: self.hard_landing_height,
+            fall_damage_height: self.fall_damage_height,
+            fall_damage: self.fall_damage,
+            fall_damage_step: self.fall_damage_step,
+        }
+    }
+
+    pub fn enemy_speeds(&self) -> enemy_physics::Speeds {
+        enemy_physics::Speeds::new(self.enemy_patrol_speed, self.enemy_aggro_speed)
+    }
+}
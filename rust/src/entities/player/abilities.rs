@@ -1,52 +1,58 @@
 use godot::{
     builtin::Vector2,
-    classes::{IStaticBody2D, StaticBody2D, Timer},
+    classes::{AnimatableBody2D, IAnimatableBody2D, Timer},
     obj::{Base, Gd, OnReady, WithBaseField},
     prelude::{GodotClass, godot_api},
 };
 
+use crate::world::moving_platform::{PathMode, PlatformMotion};
+
+/// A short lived moving platform that travels along `velocity` and back to where it spawned.
+/// Turns around when blocked, and frees itself after being blocked twice.
 #[derive(GodotClass, Debug)]
-#[class(init, base=StaticBody2D)]
+#[class(init, base=AnimatableBody2D)]
 pub struct JumpPlatform {
     pub velocity: Vector2,
     pub start: Vector2,
     collision_count: u32,
+    motion: PlatformMotion,
     #[init(node = "FreeTimer")]
     free_timer: OnReady<Gd<Timer>>,
-    #[init(node = "ChangeTimer")]
-    change_timer: OnReady<Gd<Timer>>,
-    base: Base<StaticBody2D>,
+    base: Base<AnimatableBody2D>,
 }
 
 #[godot_api]
-impl IStaticBody2D for JumpPlatform {
+impl IAnimatableBody2D for JumpPlatform {
     fn ready(&mut self) {
         self.free_timer.set_wait_time(4.0);
         self.free_timer
             .signals()
             .timeout()
             .connect_other(&self.to_gd(), Self::free);
-
-        self.change_timer.set_wait_time(2.0);
-        self.change_timer
-            .signals()
-            .timeout()
-            .connect_other(&self.to_gd(), Self::change_dir);
-
         self.free_timer.start();
-        self.change_timer.start();
-        self.start = self.base().get_position();
+
+        // Travels for two seconds before heading back.
+        let speed = self.velocity.length() * 100.0;
+        self.start = self.base().get_global_position();
+        let end = self.start + self.velocity.normalized_or_zero() * speed * 2.0;
+        self.motion = PlatformMotion::new(vec![self.start, end], PathMode::PingPong, speed, 0.0);
     }
 
     fn physics_process(&mut self, delta: f32) {
-        let velocity = self.velocity * 100.0;
+        let position = self.base().get_global_position();
+        let next = self.motion.step(position, delta);
 
-        let kin = self.base_mut().move_and_collide(velocity * delta);
-        if let Some(col) = kin
-            && let Some(obj) = col.get_collider()
-            && !obj.is_class("MainCharacter")
-        {
+        let blocked = self
+            .base_mut()
+            .move_and_collide_ex(next - position)
+            .test_only(true)
+            .done()
+            .and_then(|col| col.get_collider())
+            .is_some_and(|obj| !obj.is_class("MainCharacter"));
+        if blocked {
             self.change_dir();
+        } else {
+            self.base_mut().set_global_position(next);
         }
     }
 }
@@ -62,10 +68,51 @@ impl JumpPlatform {
         if self.collision_count == 2 {
             self.free();
         }
-        self.change_timer.stop();
-        let cur_pos = self.base().get_position();
-        self.velocity = cur_pos.direction_to(self.start);
-        let lin_vel = self.base().get_constant_linear_velocity() * -1.0;
-        self.base_mut().set_constant_linear_velocity(lin_vel);
+        self.motion.reverse();
+    }
+}
+
+/// Movement abilities that are unlocked by picking up their item.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MovementAbility {
+    DoubleJump,
+    AirDash,
+    WallGrab,
+}
+
+impl MovementAbility {
+    /// Converts the editor hint of a `GameItem`. 1 = DoubleJump, 2 = AirDash, 3 = WallGrab.
+    pub fn from_hint(hint: u32) -> Option<Self> {
+        match hint {
+            1 => Some(MovementAbility::DoubleJump),
+            2 => Some(MovementAbility::AirDash),
+            3 => Some(MovementAbility::WallGrab),
+            _ => None,
+        }
+    }
+}
+
+/// The movement abilities the player has unlocked.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MovementAbilities {
+    pub double_jump: bool,
+    pub air_dash: bool,
+    pub wall_grab: bool,
+}
+
+impl MovementAbilities {
+    pub fn unlock(&mut self, ability: MovementAbility) {
+        match ability {
+            MovementAbility::DoubleJump => self.double_jump = true,
+            MovementAbility::AirDash => self.air_dash = true,
+            MovementAbility::WallGrab => self.wall_grab = true,
+        }
+    }
+
+    /// Unlocks everything unlocked in `other`. Abilities are never lost.
+    pub fn merge(&mut self, other: MovementAbilities) {
+        self.double_jump |= other.double_jump;
+        self.air_dash |= other.air_dash;
+        self.wall_grab |= other.wall_grab;
     }
 }
@@ -1,44 +1,61 @@
-use godot::obj::Gd;
 use statig::prelude::*;
 
 use crate::{
     entities::{
         combat::{
-            offense::{HotSpellIndexer, Offense, PlayerAttacks},
+            frame_data::{AttackWindow, FrameData, PlayerFrameData},
+            offense::{HotSpellIndexer, Offense, PlayerAttacks, Spell},
             resources::CombatResources,
         },
-        graphics::Graphics,
-        hit_reg::Hurtbox,
+        graphics::{AnimationSink, Clip, StateAnimations},
+        hit_reg::AttackSlot,
         movements::Direction,
-        player::{physics::Movement, time::PlayerTimers},
-    },
-    utils::{
-        global_data_singleton::GlobalData,
-        input_hanlder::{Inputs, ModifierButton, MoveButton},
+        player::{
+            abilities::MovementAbilities,
+            physics::Movement,
+            time::{PlayerTimer, SMTimers},
+        },
     },
+    utils::input_hanlder::{InputBuffer, Inputs, ModifierButton, MoveButton},
 };
 
-// Animation player uses the implementation of `Display` for animation names.
-impl std::fmt::Display for State {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            State::Idle {} | State::WallGrab {} | State::ForcedDisabled {} => write!(f, "idle"),
-            State::Run {} => write!(f, "run"),
-            State::Dodging {} => write!(f, "dodge"),
-            State::Jumping {} => write!(f, "jumping"),
-            State::Falling {} => write!(f, "falling"),
-            State::Attacking {} => write!(f, "attack"),
-            State::Chargedattack {} => write!(f, "chargedattack"),
-            State::ChainAttack {} => write!(f, "chainattack"),
-            State::Hurt {} => write!(f, "hurt"),
-            State::Healing {} => write!(f, "heal"),
-            State::Parry {} => write!(f, "parry"),
-            State::CastSpell {} => write!(f, "cast_spell"),
-            State::AirDash {} => write!(f, "air_dash"),
-        }
-    }
+impl StateAnimations for State {
+    const ANIMATIONS: &'static [(Self, Clip)] = &[
+        (State::Idle {}, Clip::Directional("idle")),
+        (State::WallGrab {}, Clip::Directional("idle")),
+        (State::ForcedDisabled {}, Clip::Directional("idle")),
+        (State::Run {}, Clip::Directional("run")),
+        (State::Dodging {}, Clip::Directional("dodge")),
+        (State::Jumping {}, Clip::Directional("jumping")),
+        (State::Falling {}, Clip::Directional("falling")),
+        (State::Attacking {}, Clip::Directional(AIR_ATTACK_CLIP)),
+        (State::Chargedattack {}, Clip::Directional("chargedattack")),
+        (State::ChainAttack {}, Clip::Directional("chainattack")),
+        (State::Hurt {}, Clip::Directional("hurt")),
+        (State::Healing {}, Clip::Directional("heal")),
+        (State::Parry {}, Clip::Directional("parry")),
+        // TODO: Tween player sprite or add shader for casting spells.
+        (State::CastSpell {}, Clip::Keep),
+        (State::AirDash {}, Clip::Directional("air_dash")),
+        (State::Dead {}, Clip::Directional("death")),
+        (State::LedgeHang {}, Clip::Directional("ledge_hang")),
+        (State::LedgeClimb {}, Clip::Directional("ledge_climb")),
+        (State::Crouch {}, Clip::Directional("crouch_idle")),
+        (State::CrouchAttack {}, Clip::Directional("crouch_attack")),
+        (State::Slide {}, Clip::Directional("slide")),
+        (State::HardLanding {}, Clip::Directional("hard_landing")),
+        (State::Climbing {}, Clip::Directional("ladder")),
+        // There is no swimming asset yet.
+        (State::Swimming {}, Clip::Directional("falling")),
+    ];
+    const EXTRA_CLIPS: &'static [&'static str] = &[DOWN_STRIKE_CLIP];
 }
 
+/// Played by the down strike, which has no state of its own.
+pub const DOWN_STRIKE_CLIP: &str = "down_strike";
+/// Played by attacks in the air, which stay in the airborne state. Shared with `Attacking`.
+const AIR_ATTACK_CLIP: &str = "attack";
+
 impl Default for State {
     fn default() -> Self {
         State::Idle {}
@@ -49,25 +66,38 @@ impl Default for State {
 // Prevents the SM from switching states due to irrelavent timers emitting their `timeout` signal.
 pub enum Timers {
     DodgeAnimation,
-    AttackAnimation,
-    Attack2Animation,
     HealingAnimation,
     HurtAnimation,
     ParryAnimation,
     JumpLimit,
-    ChargedAttack,
     CastSpellAnimation,
+    LedgeClimbAnimation,
+    SlideAnimation,
+    HardLandingAnimation,
 }
 
 #[derive(Debug, Default, PartialEq, Clone)]
 pub enum Event {
     InputChanged(Inputs),
     TimerElapsed(Timers, Inputs),
+    /// The attack in progress reached its last frame.
+    AttackFinished(Inputs),
     FailedFloorCheck(Inputs),
     Landed(Inputs),
+    /// Landed after a long fall. Sent before `Landed`.
+    HardLanded,
     HitCeiling(Inputs),
     GrabbedWall(Inputs),
+    GrabbedLedge(Inputs),
+    GrabbedLadder(Inputs),
+    LeftLadder(Inputs),
+    EnteredWater(Inputs),
+    LeftWater(Inputs),
+    /// A down strike hit an enemy or hazard.
+    PogoBounced,
     Hurt,
+    Died,
+    Respawned,
     ForceDisabled,
     ForceEnabled,
     #[default]
@@ -75,32 +105,82 @@ pub enum Event {
 }
 
 pub struct SMContext<'a> {
-    timers: &'a mut PlayerTimers,
+    timers: &'a mut dyn SMTimers,
     resources: &'a mut CombatResources,
-    hurtbox: Gd<Hurtbox>,
+    hurtbox: &'a mut dyn AttackSlot,
+    down_hurtbox: &'a mut dyn AttackSlot,
     off: &'a Offense,
     movement: &'a mut Movement,
-    graphics: &'a mut Graphics,
+    animations: &'a mut dyn AnimationSink,
+    abilities: &'a MovementAbilities,
+    /// Recently pressed actions, used for jump buffering and attack chaining.
+    buffer: &'a mut InputBuffer,
+    /// Whether there is geometry above the player that prevents them from standing up.
+    low_ceiling: bool,
+    /// The attack in progress, which owns `hurtbox` until it ends.
+    attack: &'a mut Option<AttackWindow>,
+    frame_data: &'a PlayerFrameData,
+    /// A spell cast during this transition, for the player to spawn once it's done.
+    cast: Option<Spell>,
 }
 
 impl<'a> SMContext<'a> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        timers: &'a mut PlayerTimers,
+        timers: &'a mut dyn SMTimers,
         resources: &'a mut CombatResources,
-        hurtbox: Gd<Hurtbox>,
+        hurtbox: &'a mut dyn AttackSlot,
+        down_hurtbox: &'a mut dyn AttackSlot,
         off: &'a Offense,
         movement: &'a mut Movement,
-        graphics: &'a mut Graphics,
+        animations: &'a mut dyn AnimationSink,
+        abilities: &'a MovementAbilities,
+        buffer: &'a mut InputBuffer,
+        low_ceiling: bool,
+        attack: &'a mut Option<AttackWindow>,
+        frame_data: &'a PlayerFrameData,
     ) -> Self {
         Self {
             timers,
             resources,
             hurtbox,
+            down_hurtbox,
             off,
             movement,
-            graphics,
+            animations,
+            abilities,
+            buffer,
+            low_ceiling,
+            attack,
+            frame_data,
+            cast: None,
+        }
+    }
+
+    /// The spell cast while handling the last event, if any.
+    pub fn take_cast(&mut self) -> Option<Spell> {
+        self.cast.take()
+    }
+}
+
+/// Advances the player's attack by a frame, enabling the hurtbox for its active frames. Returns
+/// `true` once the attack is over, for the player to send `Event::AttackFinished`.
+pub fn advance_attack(attack: &mut Option<AttackWindow>, hurtbox: &mut dyn AttackSlot) -> bool {
+    let Some(window) = attack else {
+        return false;
+    };
+    let was_active = window.is_active();
+    if !window.advance() {
+        *attack = None;
+        if was_active {
+            hurtbox.set_enabled(false);
         }
+        return true;
+    }
+    if window.is_active() != was_active {
+        hurtbox.set_enabled(window.is_active());
     }
+    false
 }
 
 #[derive(Default, Debug, Clone)]
@@ -115,6 +195,9 @@ impl CharacterStateMachine {
     fn idle(event: &Event, context: &mut SMContext<'_>) -> Response<State> {
         match event {
             Event::InputChanged(inputs) => {
+                if let Ok(res) = Self::try_sliding(inputs, context) {
+                    return res;
+                }
                 if let Ok(res) = Self::try_dodging(inputs, context) {
                     return res;
                 }
@@ -124,6 +207,9 @@ impl CharacterStateMachine {
                 if let Ok(res) = Self::try_healing(inputs, context) {
                     return res;
                 }
+                if let Ok(res) = Self::try_crouch_attack(inputs, context) {
+                    return res;
+                }
                 if let Ok(res) = Self::try_attacking(inputs, context) {
                     return res;
                 }
@@ -139,11 +225,11 @@ impl CharacterStateMachine {
                     Self::to_moving(inputs, context)
                 }
             }
-            Event::FailedFloorCheck(inputs) => Self::to_falling(inputs, context),
-            Event::Hurt => {
-                context.timers.hurt_anim.start();
-                Response::Transition(State::hurt())
-            }
+            Event::FailedFloorCheck(inputs) => Self::to_coyote_falling(inputs, context),
+            Event::GrabbedLadder(inputs) => Self::handle_ladder_grab(inputs, context),
+            Event::EnteredWater(inputs) => Self::to_swimming(inputs, context),
+            Event::Hurt => Self::to_hurt(context),
+            Event::Died => Self::to_dead(context),
             Event::ForceDisabled => Response::Transition(State::forced_disabled()),
             _ => Handled,
         }
@@ -153,6 +239,9 @@ impl CharacterStateMachine {
     fn run(event: &Event, context: &mut SMContext<'_>) -> Response<State> {
         match event {
             Event::InputChanged(inputs) => {
+                if let Ok(res) = Self::try_sliding(inputs, context) {
+                    return res;
+                }
                 if let Ok(res) = Self::try_dodging(inputs, context) {
                     return res;
                 }
@@ -162,6 +251,9 @@ impl CharacterStateMachine {
                 if let Ok(res) = Self::try_healing(inputs, context) {
                     return res;
                 }
+                if let Ok(res) = Self::try_crouch_attack(inputs, context) {
+                    return res;
+                }
                 if let Ok(res) = Self::try_attacking(inputs, context) {
                     return res;
                 }
@@ -177,8 +269,11 @@ impl CharacterStateMachine {
                     Self::to_moving(inputs, context)
                 }
             }
-            Event::FailedFloorCheck(inputs) => Self::to_falling(inputs, context),
-            Event::Hurt => Response::Transition(State::hurt()),
+            Event::FailedFloorCheck(inputs) => Self::to_coyote_falling(inputs, context),
+            Event::GrabbedLadder(inputs) => Self::handle_ladder_grab(inputs, context),
+            Event::EnteredWater(inputs) => Self::to_swimming(inputs, context),
+            Event::Hurt => Self::to_hurt(context),
+            Event::Died => Self::to_dead(context),
             Event::ForceDisabled => Response::Transition(State::forced_disabled()),
             _ => Handled,
         }
@@ -191,6 +286,8 @@ impl CharacterStateMachine {
                 Self::to_moving(inputs, context)
             }
             Event::FailedFloorCheck(inputs) => Self::to_falling(inputs, context),
+            Event::EnteredWater(inputs) => Self::to_swimming(inputs, context),
+            Event::Died => Self::to_dead(context),
             Event::ForceDisabled => Response::Transition(State::forced_disabled()),
             _ => Handled,
         }
@@ -200,6 +297,9 @@ impl CharacterStateMachine {
     fn jumping(event: &Event, context: &mut SMContext<'_>) -> Response<State> {
         match event {
             Event::GrabbedWall(inputs) => Self::handle_wall_grab(inputs, context),
+            Event::GrabbedLedge(inputs) => Self::handle_ledge_grab(inputs, context),
+            Event::GrabbedLadder(inputs) => Self::handle_ladder_grab(inputs, context),
+            Event::EnteredWater(inputs) => Self::to_swimming(inputs, context),
             Event::InputChanged(inputs) => {
                 if let Ok(res) = Self::try_casting_spell(inputs, context) {
                     return res;
@@ -208,22 +308,20 @@ impl CharacterStateMachine {
                     res
                 } else if let Ok(res) = Self::try_airborne_attack(inputs, context) {
                     res
+                } else if let Some(time) = inputs.released_jump() {
+                    context.movement.apply_early_gravity(time);
+                    Response::Transition(State::falling())
                 } else {
-                    match (&inputs.0, &inputs.1) {
-                        (_, Some(ModifierButton::ReleasedJump(time))) => {
-                            context.movement.apply_early_gravity(*time);
-                            Response::Transition(State::falling())
-                        }
-
-                        (Some(MoveButton::Left), Some(ModifierButton::Jump)) => {
+                    match &inputs.movement {
+                        Some(MoveButton::Left) if inputs.has(ModifierButton::Jump) => {
                             context.movement.jump_left();
                             Handled
                         }
-                        (Some(MoveButton::Right), Some(ModifierButton::Jump)) => {
+                        Some(MoveButton::Right) if inputs.has(ModifierButton::Jump) => {
                             context.movement.jump_right();
                             Handled
                         }
-                        (_, Some(ModifierButton::Jump)) => {
+                        _ if inputs.has(ModifierButton::Jump) => {
                             context.movement.jump();
                             Handled
                         }
@@ -234,8 +332,11 @@ impl CharacterStateMachine {
             Event::TimerElapsed(timer, inputs) if *timer == Timers::JumpLimit => {
                 Self::to_falling(inputs, context)
             }
-            Event::Landed(inputs) => Self::to_moving(inputs, context),
+            Event::PogoBounced => Self::to_pogo_bounce(context),
+            Event::HardLanded => Self::to_hard_landing(context),
+            Event::Landed(inputs) => Self::to_landed(inputs, context),
             Event::HitCeiling(inputs) => Self::to_falling(inputs, context),
+            Event::Died => Self::to_dead(context),
             Event::ForceDisabled => Response::Transition(State::forced_disabled()),
             _ => Handled,
         }
@@ -245,10 +346,22 @@ impl CharacterStateMachine {
     fn falling(event: &Event, context: &mut SMContext<'_>) -> Response<State> {
         match event {
             Event::GrabbedWall(inputs) => Self::handle_wall_grab(inputs, context),
+            Event::GrabbedLedge(inputs) => Self::handle_ledge_grab(inputs, context),
+            Event::GrabbedLadder(inputs) => Self::handle_ladder_grab(inputs, context),
+            Event::EnteredWater(inputs) => Self::to_swimming(inputs, context),
             Event::InputChanged(inputs) => {
+                if inputs.released_jump().is_some() {
+                    context.movement.release_jump();
+                }
                 if let Ok(res) = Self::try_casting_spell(inputs, context) {
                     return res;
                 }
+                if let Ok(res) = Self::try_coyote_jump(inputs, context) {
+                    return res;
+                }
+                if let Ok(res) = Self::try_double_jump(inputs, context) {
+                    return res;
+                }
                 if let Ok(res) = Self::try_air_dash(inputs, context) {
                     res
                 } else if let Ok(res) = Self::try_airborne_attack(inputs, context) {
@@ -257,7 +370,10 @@ impl CharacterStateMachine {
                     Self::handled_movement_input(inputs, context)
                 }
             }
-            Event::Landed(inputs) => Self::to_moving(inputs, context),
+            Event::PogoBounced => Self::to_pogo_bounce(context),
+            Event::HardLanded => Self::to_hard_landing(context),
+            Event::Landed(inputs) => Self::to_landed(inputs, context),
+            Event::Died => Self::to_dead(context),
             Event::ForceDisabled => Response::Transition(State::forced_disabled()),
             _ => Handled,
         }
@@ -266,27 +382,21 @@ impl CharacterStateMachine {
     #[state]
     fn attacking(event: &Event, context: &mut SMContext<'_>) -> Response<State> {
         match event {
-            Event::TimerElapsed(timer, inputs) if *timer == Timers::AttackAnimation => {
-                match (&inputs.0, &inputs.1) {
-                    (_, Some(ModifierButton::Attack))
-                        if context.timers.attack_2_anim.is_stopped()
-                            && let Ok(attack) = Offense::try_attack(
-                                PlayerAttacks::ChargedMelee,
-                                context.resources,
-                                1,
-                            ) =>
-                    {
-                        context.hurtbox.bind_mut().set_attack(attack);
-                        context.timers.attack_2_anim.start();
-                        Response::Transition(State::chain_attack())
-                    }
-                    _ => Self::to_moving(inputs, context),
-                }
+            Event::AttackFinished(inputs) => Self::try_chain_attack(inputs, context)
+                .unwrap_or_else(|_| Self::to_moving(inputs, context)),
+            Event::InputChanged(inputs) if Self::can_cancel(context) => {
+                Self::try_dodge_cancel(inputs, context)
+                    .or_else(|_| Self::try_chain_attack(inputs, context))
+                    .unwrap_or_else(|_| Self::handled_movement_input(inputs, context))
             }
             Event::InputChanged(inputs) => Self::handled_movement_input(inputs, context),
-            Event::Landed(inputs) => Self::to_moving(inputs, context),
-            Event::Hurt => Response::Transition(State::hurt()),
-            Event::ForceDisabled => Response::Transition(State::forced_disabled()),
+            Event::Landed(inputs) => {
+                Self::end_attack(context);
+                Self::to_moving(inputs, context)
+            }
+            Event::Hurt => Self::to_hurt(context),
+            Event::Died => Self::to_dead(context),
+            Event::ForceDisabled => Self::to_forced_disabled(context),
             _ => Handled,
         }
     }
@@ -294,9 +404,11 @@ impl CharacterStateMachine {
     #[state]
     fn chargedattack(event: &Event, context: &mut SMContext<'_>) -> Response<State> {
         match event {
-            Event::TimerElapsed(timer, inputs) if *timer == Timers::ChargedAttack => {
-                Self::to_moving(inputs, context)
+            Event::AttackFinished(inputs) => Self::to_moving(inputs, context),
+            Event::InputChanged(inputs) if Self::can_cancel(context) => {
+                Self::try_dodge_cancel(inputs, context).unwrap_or(Handled)
             }
+            Event::Died => Self::to_dead(context),
             _ => Handled,
         }
     }
@@ -304,11 +416,13 @@ impl CharacterStateMachine {
     #[state]
     fn chain_attack(event: &Event, context: &mut SMContext<'_>) -> Response<State> {
         match event {
-            Event::TimerElapsed(timer, inputs) if *timer == Timers::Attack2Animation => {
-                Self::to_moving(inputs, context)
+            Event::AttackFinished(inputs) => Self::to_moving(inputs, context),
+            Event::InputChanged(inputs) if Self::can_cancel(context) => {
+                Self::try_dodge_cancel(inputs, context).unwrap_or(Handled)
             }
-            Event::Hurt => Response::Transition(State::hurt()),
-            Event::ForceDisabled => Response::Transition(State::forced_disabled()),
+            Event::Hurt => Self::to_hurt(context),
+            Event::Died => Self::to_dead(context),
+            Event::ForceDisabled => Self::to_forced_disabled(context),
             _ => Handled,
         }
     }
@@ -319,6 +433,7 @@ impl CharacterStateMachine {
             Event::TimerElapsed(timer, inputs) if *timer == Timers::HurtAnimation => {
                 Self::to_moving(inputs, context)
             }
+            Event::Died => Self::to_dead(context),
             Event::ForceDisabled => Response::Transition(State::forced_disabled()),
             _ => Handled,
         }
@@ -328,9 +443,10 @@ impl CharacterStateMachine {
     fn healing(event: &Event, context: &mut SMContext<'_>) -> Response<State> {
         match event {
             Event::TimerElapsed(timer, inputs) if *timer == Timers::HealingAnimation => {
-                context.timers.healing_cooldown.start();
+                context.timers.start(PlayerTimer::HealingCooldown);
                 Self::to_moving(inputs, context)
             }
+            Event::Died => Self::to_dead(context),
             Event::ForceDisabled => Response::Transition(State::forced_disabled()),
             _ => Handled,
         }
@@ -342,15 +458,17 @@ impl CharacterStateMachine {
             Event::TimerElapsed(timer, inputs) if *timer == Timers::ParryAnimation => {
                 Self::to_moving(inputs, context)
             }
+            Event::Died => Self::to_dead(context),
             Event::ForceDisabled => Response::Transition(State::forced_disabled()),
             _ => Handled,
         }
     }
 
     #[state]
-    fn forced_disabled(event: &Event) -> Response<State> {
+    fn forced_disabled(event: &Event, context: &mut SMContext<'_>) -> Response<State> {
         match event {
             Event::ForceEnabled => Response::Transition(State::idle()),
+            Event::Died => Self::to_dead(context),
             _ => Handled,
         }
     }
@@ -358,26 +476,30 @@ impl CharacterStateMachine {
     #[state]
     fn wall_grab(event: &Event, context: &mut SMContext<'_>) -> Response<State> {
         match event {
-            Event::InputChanged(inputs) => match (&inputs.0, inputs.1) {
-                (Some(MoveButton::Left), Some(ModifierButton::Jump))
-                    if context.timers.wall_jump.is_stopped() =>
+            Event::InputChanged(inputs) => match &inputs.movement {
+                Some(MoveButton::Left)
+                    if inputs.has(ModifierButton::Jump)
+                        && context.timers.is_stopped(PlayerTimer::WallJump) =>
                 {
-                    context.timers.wall_jump.start();
+                    context.timers.start(PlayerTimer::WallJump);
                     context.movement.jump_right();
                     Response::Transition(State::jumping())
                 }
-                (Some(MoveButton::Right), Some(ModifierButton::Jump))
-                    if context.timers.wall_jump.is_stopped() =>
+                Some(MoveButton::Right)
+                    if inputs.has(ModifierButton::Jump)
+                        && context.timers.is_stopped(PlayerTimer::WallJump) =>
                 {
-                    context.timers.wall_jump.start();
+                    context.timers.start(PlayerTimer::WallJump);
                     context.movement.jump_left();
                     Response::Transition(State::jumping())
                 }
-                (Some(MoveButton::Right), _) => Response::Transition(State::falling()),
+                Some(MoveButton::Right) => Response::Transition(State::falling()),
                 _ => Self::to_falling(inputs, context),
             },
-            Event::Landed(inputs) => Self::to_moving(inputs, context),
+            Event::GrabbedLedge(inputs) => Self::handle_ledge_grab(inputs, context),
+            Event::Landed(inputs) => Self::to_landed(inputs, context),
             Event::Hurt => Response::Transition(State::falling()),
+            Event::Died => Self::to_dead(context),
             _ => Handled,
         }
     }
@@ -390,6 +512,7 @@ impl CharacterStateMachine {
             Event::TimerElapsed(timer, inputs) if *timer == Timers::CastSpellAnimation => {
                 Self::to_moving(inputs, context)
             }
+            Event::Died => Self::to_dead(context),
             _ => Handled,
         }
     }
@@ -401,7 +524,195 @@ impl CharacterStateMachine {
             Event::TimerElapsed(timer, inputs) if *timer == Timers::DodgeAnimation => {
                 Self::to_falling(inputs, context)
             }
+            Event::EnteredWater(inputs) => Self::to_swimming(inputs, context),
+            Event::Landed(inputs) => Self::to_landed(inputs, context),
+            Event::Died => Self::to_dead(context),
+            _ => Handled,
+        }
+    }
+
+    /// Hanging from the edge of a wall. Holding towards the ledge climbs up, holding away drops
+    /// down and jumping jumps off.
+    #[state]
+    fn ledge_hang(event: &Event, context: &mut SMContext<'_>) -> Response<State> {
+        match event {
+            Event::InputChanged(inputs) => {
+                let ledge = context.movement.get_direction();
+                match (&inputs.movement, ledge) {
+                    _ if inputs.has(ModifierButton::Jump) => {
+                        context.movement.jump();
+                        context.timers.start(PlayerTimer::JumpLimit);
+                        Response::Transition(State::jumping())
+                    }
+                    (Some(MoveButton::Left), Direction::Left)
+                    | (Some(MoveButton::Right), Direction::Right) => {
+                        context.timers.start(PlayerTimer::LedgeClimbAnim);
+                        Response::Transition(State::ledge_climb())
+                    }
+                    (Some(MoveButton::Left | MoveButton::Right | MoveButton::Down), _) => {
+                        Self::to_falling(inputs, context)
+                    }
+                    _ => Handled,
+                }
+            }
+            Event::Hurt => Response::Transition(State::falling()),
+            Event::Died => Self::to_dead(context),
+            Event::ForceDisabled => Response::Transition(State::forced_disabled()),
+            _ => Handled,
+        }
+    }
+
+    /// Climbing over a ledge. The player is moved on top of the ledge when the animation ends.
+    #[state]
+    fn ledge_climb(event: &Event, context: &mut SMContext<'_>) -> Response<State> {
+        match event {
+            Event::TimerElapsed(timer, inputs) if *timer == Timers::LedgeClimbAnimation => {
+                Self::to_moving(inputs, context)
+            }
+            Event::Died => Self::to_dead(context),
+            _ => Handled,
+        }
+    }
+
+    #[state]
+    fn crouch(event: &Event, context: &mut SMContext<'_>) -> Response<State> {
+        match event {
+            Event::InputChanged(inputs) => {
+                if let Ok(res) = Self::try_sliding(inputs, context) {
+                    return res;
+                }
+                if let Ok(res) = Self::try_crouch_attack(inputs, context) {
+                    return res;
+                }
+                Self::to_moving(inputs, context)
+            }
+            Event::FailedFloorCheck(inputs) => Self::to_falling(inputs, context),
+            Event::EnteredWater(inputs) => Self::to_swimming(inputs, context),
+            Event::Hurt => Self::to_hurt(context),
+            Event::Died => Self::to_dead(context),
+            Event::ForceDisabled => Response::Transition(State::forced_disabled()),
+            _ => Handled,
+        }
+    }
+
+    #[state]
+    fn crouch_attack(event: &Event, context: &mut SMContext<'_>) -> Response<State> {
+        match event {
+            Event::AttackFinished(inputs) => Self::to_moving(inputs, context),
+            Event::InputChanged(inputs) if Self::can_cancel(context) => {
+                Self::try_dodge_cancel(inputs, context).unwrap_or(Handled)
+            }
+            Event::FailedFloorCheck(inputs) => {
+                Self::end_attack(context);
+                Self::to_falling(inputs, context)
+            }
+            Event::Hurt => Self::to_hurt(context),
+            Event::Died => Self::to_dead(context),
+            Event::ForceDisabled => Self::to_forced_disabled(context),
+            _ => Handled,
+        }
+    }
+
+    /// A low dodge that fits under gaps the player can't stand in.
+    #[state]
+    fn slide(event: &Event, context: &mut SMContext<'_>) -> Response<State> {
+        match event {
+            Event::TimerElapsed(timer, inputs) if *timer == Timers::SlideAnimation => {
+                Self::to_moving(inputs, context)
+            }
+            Event::FailedFloorCheck(inputs) => Self::to_falling(inputs, context),
+            Event::EnteredWater(inputs) => Self::to_swimming(inputs, context),
+            Event::Hurt => Self::to_hurt(context),
+            Event::Died => Self::to_dead(context),
+            Event::ForceDisabled => Response::Transition(State::forced_disabled()),
+            _ => Handled,
+        }
+    }
+
+    /// Recovering from a long fall. Input is ignored until the animation ends.
+    #[state]
+    fn hard_landing(event: &Event, context: &mut SMContext<'_>) -> Response<State> {
+        match event {
+            Event::TimerElapsed(timer, inputs) if *timer == Timers::HardLandingAnimation => {
+                Self::to_moving(inputs, context)
+            }
+            Event::FailedFloorCheck(inputs) => Self::to_coyote_falling(inputs, context),
+            Event::EnteredWater(inputs) => Self::to_swimming(inputs, context),
+            Event::Hurt => Self::to_hurt(context),
+            Event::Died => Self::to_dead(context),
+            Event::ForceDisabled => Response::Transition(State::forced_disabled()),
+            _ => Handled,
+        }
+    }
+
+    /// Climbing a ladder. Attacks and spells are unavailable, jumping lets go of the ladder.
+    #[state]
+    fn climbing(event: &Event, context: &mut SMContext<'_>) -> Response<State> {
+        match event {
+            Event::InputChanged(inputs) => match &inputs.movement {
+                Some(MoveButton::Left) if inputs.has(ModifierButton::Jump) => {
+                    context.movement.jump_left();
+                    context.timers.start(PlayerTimer::JumpLimit);
+                    Response::Transition(State::jumping())
+                }
+                Some(MoveButton::Right) if inputs.has(ModifierButton::Jump) => {
+                    context.movement.jump_right();
+                    context.timers.start(PlayerTimer::JumpLimit);
+                    Response::Transition(State::jumping())
+                }
+                _ if inputs.has(ModifierButton::Jump) => {
+                    context.movement.jump();
+                    context.timers.start(PlayerTimer::JumpLimit);
+                    Response::Transition(State::jumping())
+                }
+                movement => {
+                    context.movement.climb(*movement);
+                    Handled
+                }
+            },
+            Event::LeftLadder(inputs) => Self::to_falling(inputs, context),
             Event::Landed(inputs) => Self::to_moving(inputs, context),
+            Event::Hurt => Response::Transition(State::falling()),
+            Event::Died => Self::to_dead(context),
+            Event::ForceDisabled => Response::Transition(State::forced_disabled()),
+            _ => Handled,
+        }
+    }
+
+    /// Submerged in water. Jumping swims upwards and buoyancy counters gravity.
+    #[state]
+    fn swimming(event: &Event, context: &mut SMContext<'_>) -> Response<State> {
+        match event {
+            Event::InputChanged(inputs) => {
+                if let Ok(res) = Self::try_airborne_attack(inputs, context) {
+                    return res;
+                }
+                if inputs.has(ModifierButton::Jump) {
+                    context.movement.swim_stroke();
+                }
+                context.movement.swim(Self::input_direction(inputs));
+                Handled
+            }
+            Event::LeftWater(inputs) => match &inputs.movement {
+                _ if inputs.has(ModifierButton::Jump) => {
+                    context.movement.jump();
+                    context.movement.swim(Self::input_direction(inputs));
+                    context.timers.start(PlayerTimer::JumpLimit);
+                    Response::Transition(State::jumping())
+                }
+                _ => Self::to_falling(inputs, context),
+            },
+            Event::Died => Self::to_dead(context),
+            Event::ForceDisabled => Response::Transition(State::forced_disabled()),
+            _ => Handled,
+        }
+    }
+
+    /// Terminal state until the player is respawned. Input is ignored.
+    #[state]
+    fn dead(event: &Event) -> Response<State> {
+        match event {
+            Event::Respawned => Response::Transition(State::idle()),
             _ => Handled,
         }
     }
@@ -414,14 +725,9 @@ impl CharacterStateMachine {
         if let Some(spell) = spell {
             let mut attack = spell.attack(1);
             if Offense::check_resources(attack.cost(), context.resources).is_ok() {
-                let scene = spell.init_scene();
                 context.off.apply_buffs(&mut attack);
-                GlobalData::singleton()
-                    .bind_mut()
-                    .get_player_mut()
-                    .unwrap()
-                    .add_sibling(&scene);
-                context.timers.spell_cooldown.start();
+                context.cast = Some(spell);
+                context.timers.start(PlayerTimer::SpellCooldown);
                 Ok(())
             } else {
                 Err(())
@@ -435,65 +741,58 @@ impl CharacterStateMachine {
         inputs: &Inputs,
         context: &mut SMContext<'_>,
     ) -> Result<Response<State>, ()> {
-        match (&inputs.1, &inputs.2) {
-            (
-                Some(ModifierButton::Ability1 | ModifierButton::Jump),
-                Some(ModifierButton::Jump | ModifierButton::Ability1),
-            ) if context.timers.spell_cooldown.is_stopped()
-                && Self::try_cast_spell(context, HotSpellIndexer::Ability1).is_ok() =>
-            {
-                context.timers.spell_cooldown.start();
-                context.timers.cast_spell_anim.start();
-                Ok(Response::Transition(State::cast_spell()))
-            }
-            (
-                Some(ModifierButton::Ability2 | ModifierButton::Jump),
-                Some(ModifierButton::Jump | ModifierButton::Ability2),
-            ) if context.timers.spell_cooldown.is_stopped()
-                && Self::try_cast_spell(context, HotSpellIndexer::Ability2).is_ok() =>
-            {
-                context.timers.spell_cooldown.start();
-                context.timers.cast_spell_anim.start();
-                Ok(Response::Transition(State::cast_spell()))
-            }
-            (
-                Some(ModifierButton::Ability3 | ModifierButton::Jump),
-                Some(ModifierButton::Jump | ModifierButton::Ability3),
-            ) if context.timers.spell_cooldown.is_stopped()
-                && Self::try_cast_spell(context, HotSpellIndexer::Ability3).is_ok() =>
-            {
-                context.timers.spell_cooldown.start();
-                context.timers.cast_spell_anim.start();
-                Ok(Response::Transition(State::cast_spell()))
-            }
-            _ => Err(()),
+        if !context.timers.is_stopped(PlayerTimer::SpellCooldown) {
+            return Err(());
         }
+        let idx = if inputs.has(ModifierButton::CastSpell) {
+            context.off.selected_spell()
+        } else if !inputs.has(ModifierButton::Jump) {
+            return Err(());
+        } else if inputs.has(ModifierButton::Ability1) {
+            HotSpellIndexer::Ability1
+        } else if inputs.has(ModifierButton::Ability2) {
+            HotSpellIndexer::Ability2
+        } else if inputs.has(ModifierButton::Ability3) {
+            HotSpellIndexer::Ability3
+        } else {
+            return Err(());
+        };
+        Self::try_cast_spell(context, idx)?;
+        context.timers.start(PlayerTimer::SpellCooldown);
+        context.timers.start(PlayerTimer::CastSpellAnim);
+        Ok(Response::Transition(State::cast_spell()))
     }
 
     fn try_dodging(inputs: &Inputs, context: &mut SMContext<'_>) -> Result<Response<State>, ()> {
-        match (&inputs.0, &inputs.1) {
-            (Some(MoveButton::Left), Some(ModifierButton::Dodge))
-                if context.timers.dodge_cooldown.is_stopped() =>
+        match &inputs.movement {
+            Some(MoveButton::Left)
+                if inputs.has(ModifierButton::Dodge)
+                    && context.timers.is_stopped(PlayerTimer::DodgeCooldown) =>
             {
-                context.timers.dodge_cooldown.start();
+                context.timers.start(PlayerTimer::DodgeCooldown);
+                context.timers.start(PlayerTimer::DodgeAnim);
                 context.movement.dodge_left();
                 Ok(Response::Transition(State::dodging()))
             }
-            (Some(MoveButton::Right), Some(ModifierButton::Dodge))
-                if context.timers.dodge_cooldown.is_stopped() =>
+            Some(MoveButton::Right)
+                if inputs.has(ModifierButton::Dodge)
+                    && context.timers.is_stopped(PlayerTimer::DodgeCooldown) =>
             {
-                context.timers.dodge_cooldown.start();
+                context.timers.start(PlayerTimer::DodgeCooldown);
+                context.timers.start(PlayerTimer::DodgeAnim);
                 context.movement.dodge_right();
                 Ok(Response::Transition(State::dodging()))
             }
-            (None, Some(ModifierButton::Dodge)) if context.timers.dodge_cooldown.is_stopped() => {
+            None if inputs.has(ModifierButton::Dodge)
+                && context.timers.is_stopped(PlayerTimer::DodgeCooldown) =>
+            {
                 let dir = context.movement.get_direction();
                 match dir {
                     Direction::Right => context.movement.dodge_right(),
                     Direction::Left => context.movement.dodge_left(),
                 }
-                context.timers.dodge_cooldown.start();
-                context.timers.dodge_anim.start();
+                context.timers.start(PlayerTimer::DodgeCooldown);
+                context.timers.start(PlayerTimer::DodgeAnim);
                 Ok(Response::Transition(State::dodging()))
             }
 
@@ -501,40 +800,118 @@ impl CharacterStateMachine {
         }
     }
 
+    fn try_sliding(inputs: &Inputs, context: &mut SMContext<'_>) -> Result<Response<State>, ()> {
+        match &inputs.movement {
+            Some(MoveButton::Down)
+                if inputs.has(ModifierButton::Dodge)
+                    && context.timers.is_stopped(PlayerTimer::DodgeCooldown) =>
+            {
+                let dir = context.movement.get_direction();
+                context.movement.slide(dir);
+                context.timers.start(PlayerTimer::DodgeCooldown);
+                context.timers.start(PlayerTimer::SlideAnim);
+                Ok(Response::Transition(State::slide()))
+            }
+            _ => Err(()),
+        }
+    }
+
+    fn try_crouch_attack(
+        inputs: &Inputs,
+        context: &mut SMContext<'_>,
+    ) -> Result<Response<State>, ()> {
+        match &inputs.movement {
+            Some(MoveButton::Down)
+                if inputs.has(ModifierButton::Attack)
+                    && context.attack.is_none()
+                    && let Ok(attack) =
+                        Offense::try_attack(PlayerAttacks::SimpleMelee, context.resources, 1) =>
+            {
+                context.movement.stop_x();
+                context.hurtbox.set_attack(attack);
+                Self::start_attack(context.frame_data.crouch_attack, context);
+                Ok(Response::Transition(State::crouch_attack()))
+            }
+            _ => Err(()),
+        }
+    }
+
     fn try_jumping(inputs: &Inputs, context: &mut SMContext<'_>) -> Result<Response<State>, ()> {
-        if context.timers.jump_limit.is_stopped() {
-            match (&inputs.0, &inputs.1) {
-                (Some(MoveButton::Right), Some(ModifierButton::Jump)) => {
-                    context.movement.jump_right();
-                    context.timers.jump_limit.start();
-                    Ok(Response::Transition(State::jumping()))
-                }
-                (Some(MoveButton::Left), Some(ModifierButton::Jump)) => {
-                    context.movement.jump_left();
-                    context.timers.jump_limit.start();
-                    Ok(Response::Transition(State::jumping()))
-                }
-                (None, Some(ModifierButton::Jump)) => {
-                    context.movement.jump();
-                    context.timers.jump_limit.start();
-                    Ok(Response::Transition(State::jumping()))
-                }
-                _ => Err(()),
+        if context.timers.is_stopped(PlayerTimer::JumpLimit) && inputs.has(ModifierButton::Jump) {
+            match &inputs.movement {
+                Some(MoveButton::Right) => context.movement.jump_right(),
+                Some(MoveButton::Left) => context.movement.jump_left(),
+                None => context.movement.jump(),
+                _ => return Err(()),
             }
+            // The press that caused this jump shouldn't also trigger a buffered jump on landing.
+            context.buffer.consume(ModifierButton::Jump);
+            context.timers.start(PlayerTimer::JumpLimit);
+            Ok(Response::Transition(State::jumping()))
         } else {
             Err(())
         }
     }
 
+    /// Allows jumping shortly after walking off a ledge.
+    fn try_coyote_jump(
+        inputs: &Inputs,
+        context: &mut SMContext<'_>,
+    ) -> Result<Response<State>, ()> {
+        if context.timers.is_stopped(PlayerTimer::Coyote) {
+            return Err(());
+        }
+        let res = Self::try_jumping(inputs, context)?;
+        context.timers.stop(PlayerTimer::Coyote);
+        Ok(res)
+    }
+
+    /// Jumps if jump was pressed shortly before landing.
+    fn try_buffered_jump(
+        inputs: &Inputs,
+        context: &mut SMContext<'_>,
+    ) -> Result<Response<State>, ()> {
+        if !context.buffer.consume(ModifierButton::Jump) {
+            return Err(());
+        }
+        Self::try_jumping(&inputs.with(ModifierButton::Jump), context)
+    }
+
+    /// Jumps again while airborne. The jump button must have been released since the last jump.
+    fn try_double_jump(
+        inputs: &Inputs,
+        context: &mut SMContext<'_>,
+    ) -> Result<Response<State>, ()> {
+        if !context.abilities.double_jump || !context.movement.can_double_jump() {
+            return Err(());
+        }
+        match &inputs.movement {
+            Some(MoveButton::Right) if inputs.has(ModifierButton::Jump) => {
+                context.movement.jump_right();
+            }
+            Some(MoveButton::Left) if inputs.has(ModifierButton::Jump) => {
+                context.movement.jump_left();
+            }
+            None if inputs.has(ModifierButton::Jump) => {
+                context.movement.jump();
+            }
+            _ => return Err(()),
+        }
+        context.movement.double_jump();
+        context.timers.start(PlayerTimer::JumpLimit);
+        Ok(Response::Transition(State::jumping()))
+    }
+
     fn try_healing(inputs: &Inputs, context: &mut SMContext<'_>) -> Result<Response<State>, ()> {
-        if context.timers.healing_anim.is_stopped()
-            && context.timers.healing_cooldown.is_stopped()
+        if context.timers.is_stopped(PlayerTimer::HealingAnim)
+            && context.timers.is_stopped(PlayerTimer::HealingCooldown)
             && context.resources.health().amount() < context.resources.health().max()
+            && context.resources.health().heal_charges() > 0
         {
-            match (&inputs.0, &inputs.1) {
-                (_, Some(ModifierButton::Heal)) => {
-                    context.timers.healing_anim.start();
-                    context.timers.healing_cooldown.start();
+            match &inputs.movement {
+                _ if inputs.has(ModifierButton::Heal) => {
+                    context.timers.start(PlayerTimer::HealingAnim);
+                    context.timers.start(PlayerTimer::HealingCooldown);
                     context.movement.stop_x();
                     Ok(Response::Transition(State::healing()))
                 }
@@ -545,14 +922,14 @@ impl CharacterStateMachine {
         }
     }
     fn try_attacking(inputs: &Inputs, context: &mut SMContext<'_>) -> Result<Response<State>, ()> {
-        match (&inputs.0, &inputs.1) {
-            (_, Some(ModifierButton::Attack))
-                if context.timers.attack_anim.is_stopped()
-                    && let Ok(attack) =
-                        Offense::try_attack(PlayerAttacks::SimpleMelee, context.resources, 1) =>
+        match &inputs.movement {
+            _ if inputs.has(ModifierButton::Attack)
+                && context.attack.is_none()
+                && let Ok(attack) =
+                    Offense::try_attack(PlayerAttacks::SimpleMelee, context.resources, 1) =>
             {
-                context.hurtbox.bind_mut().set_attack(attack);
-                context.timers.attack_anim.start();
+                context.hurtbox.set_attack(attack);
+                Self::start_attack(context.frame_data.attack, context);
                 Ok(Response::Transition(State::attacking()))
             }
             _ => Err(()),
@@ -560,12 +937,14 @@ impl CharacterStateMachine {
     }
 
     fn try_parry(inputs: &Inputs, context: &mut SMContext<'_>) -> Result<Response<State>, ()> {
-        match (&inputs.0, &inputs.1) {
-            (_, Some(ModifierButton::Parry)) if context.timers.parry_anim.is_stopped() => {
+        match &inputs.movement {
+            _ if inputs.has(ModifierButton::Parry)
+                && context.timers.is_stopped(PlayerTimer::ParryAnim) =>
+            {
                 context.movement.stop_x();
-                context.timers.parry_anim.start();
-                context.timers.perfect_parry.start();
-                context.timers.parry.start();
+                context.timers.start(PlayerTimer::ParryAnim);
+                context.timers.start(PlayerTimer::PerfectParry);
+                context.timers.start(PlayerTimer::Parry);
                 Ok(Response::Transition(State::parry()))
             }
             _ => Err(()),
@@ -576,14 +955,14 @@ impl CharacterStateMachine {
         inputs: &Inputs,
         context: &mut SMContext<'_>,
     ) -> Result<Response<State>, ()> {
-        match (&inputs.0, &inputs.1) {
-            (_, Some(ModifierButton::ChargedAttack))
-                if context.timers.charged_attack_anim.is_stopped()
-                    && let Ok(attack) =
-                        Offense::try_attack(PlayerAttacks::ChargedMelee, context.resources, 1) =>
+        match &inputs.movement {
+            _ if inputs.has(ModifierButton::ChargedAttack)
+                && context.attack.is_none()
+                && let Ok(attack) =
+                    Offense::try_attack(PlayerAttacks::ChargedMelee, context.resources, 1) =>
             {
-                context.hurtbox.bind_mut().set_attack(attack);
-                context.timers.charged_attack_anim.start();
+                context.hurtbox.set_attack(attack);
+                Self::start_attack(context.frame_data.charged_attack, context);
                 Ok(Response::Transition(State::chargedattack()))
             }
             _ => Err(()),
@@ -591,21 +970,26 @@ impl CharacterStateMachine {
     }
 
     fn try_air_dash(inputs: &Inputs, context: &mut SMContext<'_>) -> Result<Response<State>, ()> {
-        match (&inputs.0, &inputs.1) {
-            (Some(MoveButton::Left), Some(ModifierButton::Dodge))
-                if context.timers.dodge_cooldown.is_stopped() =>
+        if !context.abilities.air_dash {
+            return Err(());
+        }
+        match &inputs.movement {
+            Some(MoveButton::Left)
+                if inputs.has(ModifierButton::Dodge)
+                    && context.timers.is_stopped(PlayerTimer::DodgeCooldown) =>
             {
-                context.timers.dodge_cooldown.start();
-                context.timers.dodge_anim.start();
+                context.timers.start(PlayerTimer::DodgeCooldown);
+                context.timers.start(PlayerTimer::DodgeAnim);
                 context.movement.air_dash_left();
                 Ok(Response::Transition(State::air_dash()))
             }
-            (Some(MoveButton::Right), Some(ModifierButton::Dodge))
-                if context.timers.dodge_cooldown.is_stopped() =>
+            Some(MoveButton::Right)
+                if inputs.has(ModifierButton::Dodge)
+                    && context.timers.is_stopped(PlayerTimer::DodgeCooldown) =>
             {
                 context.movement.air_dash_right();
-                context.timers.dodge_cooldown.start();
-                context.timers.dodge_anim.start();
+                context.timers.start(PlayerTimer::DodgeCooldown);
+                context.timers.start(PlayerTimer::DodgeAnim);
                 Ok(Response::Transition(State::air_dash()))
             }
             _ => Err(()),
@@ -613,34 +997,147 @@ impl CharacterStateMachine {
     }
 
     fn to_falling(inputs: &Inputs, context: &mut SMContext<'_>) -> Response<State> {
-        match (&inputs.0, &inputs.1) {
-            (Some(MoveButton::Left), _) => {
+        match &inputs.movement {
+            Some(MoveButton::Left) => {
                 context.movement.run_left();
                 Response::Transition(State::falling())
             }
-            (Some(MoveButton::Right), _) => {
+            Some(MoveButton::Right) => {
                 context.movement.run_right();
                 Response::Transition(State::falling())
             }
-            (None, _) => {
+            None | Some(MoveButton::Down | MoveButton::Up) => {
                 context.movement.stop_x();
                 Response::Transition(State::falling())
             }
         }
     }
 
-    /// Transitions the SM after checking movement input.
+    /// Falls after walking off a ledge, starting the coyote time window.
+    fn to_coyote_falling(inputs: &Inputs, context: &mut SMContext<'_>) -> Response<State> {
+        context.timers.start(PlayerTimer::Coyote);
+        Self::to_falling(inputs, context)
+    }
+
+    fn to_landed(inputs: &Inputs, context: &mut SMContext<'_>) -> Response<State> {
+        Self::end_attack(context);
+        Self::try_buffered_jump(inputs, context)
+            .unwrap_or_else(|_| Self::to_moving(inputs, context))
+    }
+
+    fn to_swimming(inputs: &Inputs, context: &mut SMContext<'_>) -> Response<State> {
+        Self::end_attack(context);
+        context.movement.swim(Self::input_direction(inputs));
+        Response::Transition(State::swimming())
+    }
+
+    fn input_direction(inputs: &Inputs) -> Option<Direction> {
+        match inputs.movement {
+            Some(MoveButton::Left) => Some(Direction::Left),
+            Some(MoveButton::Right) => Some(Direction::Right),
+            _ => None,
+        }
+    }
+
+    fn to_hard_landing(context: &mut SMContext<'_>) -> Response<State> {
+        Self::end_attack(context);
+        context.movement.stop_x();
+        context.buffer.consume(ModifierButton::Jump);
+        context.timers.start(PlayerTimer::HardLandingAnim);
+        Response::Transition(State::hard_landing())
+    }
+
+    /// Bounces off of whatever a down strike hit, allowing another air dash straight away.
+    fn to_pogo_bounce(context: &mut SMContext<'_>) -> Response<State> {
+        context.movement.pogo_bounce();
+        context.timers.stop(PlayerTimer::DodgeCooldown);
+        context.timers.stop(PlayerTimer::JumpLimit);
+        Response::Transition(State::falling())
+    }
+
+    /// The next combo step, if attack is held or buffered.
+    fn try_chain_attack(
+        inputs: &Inputs,
+        context: &mut SMContext<'_>,
+    ) -> Result<Response<State>, ()> {
+        if !(inputs.has(ModifierButton::Attack) || context.buffer.consume(ModifierButton::Attack)) {
+            return Err(());
+        }
+        let attack = Offense::try_attack(PlayerAttacks::ChargedMelee, context.resources, 1)?;
+        context.hurtbox.set_attack(attack);
+        Self::start_attack(context.frame_data.chain_attack, context);
+        Ok(Response::Transition(State::chain_attack()))
+    }
+
+    /// Ends the attack in progress early with a dodge.
+    fn try_dodge_cancel(
+        inputs: &Inputs,
+        context: &mut SMContext<'_>,
+    ) -> Result<Response<State>, ()> {
+        if !inputs.has(ModifierButton::Dodge) {
+            return Err(());
+        }
+        let res = Self::try_dodging(inputs, context)?;
+        Self::end_attack(context);
+        Ok(res)
+    }
+
+    /// Whether the attack in progress has reached its cancel window.
+    fn can_cancel(context: &SMContext<'_>) -> bool {
+        context.attack.is_some_and(|attack| attack.can_cancel())
+    }
+
+    fn start_attack(data: FrameData, context: &mut SMContext<'_>) {
+        let attack = AttackWindow::new(data);
+        context.hurtbox.set_enabled(attack.is_active());
+        *context.attack = Some(attack);
+    }
+
+    fn end_attack(context: &mut SMContext<'_>) {
+        if context.attack.take().is_some() {
+            context.hurtbox.set_enabled(false);
+        }
+    }
+
+    fn to_forced_disabled(context: &mut SMContext<'_>) -> Response<State> {
+        Self::end_attack(context);
+        Response::Transition(State::forced_disabled())
+    }
+
+    fn to_hurt(context: &mut SMContext<'_>) -> Response<State> {
+        Self::end_attack(context);
+        context.timers.start(PlayerTimer::HurtAnim);
+        Response::Transition(State::hurt())
+    }
+
+    fn to_dead(context: &mut SMContext<'_>) -> Response<State> {
+        Self::end_attack(context);
+        context.movement.stop_x();
+        context.timers.start(PlayerTimer::DeathAnim);
+        Response::Transition(State::dead())
+    }
+
+    /// Transitions the SM after checking movement input. The player stays crouched while under
+    /// a low ceiling.
     fn to_moving(inputs: &Inputs, context: &mut SMContext<'_>) -> Response<State> {
-        match (&inputs.0, &inputs.1) {
-            (Some(MoveButton::Left), _) => {
+        match &inputs.movement {
+            Some(MoveButton::Down) => {
+                context.movement.stop_x();
+                Response::Transition(State::crouch())
+            }
+            _ if context.low_ceiling => {
+                context.movement.stop_x();
+                Response::Transition(State::crouch())
+            }
+            Some(MoveButton::Left) => {
                 context.movement.run_left();
                 Response::Transition(State::run())
             }
-            (Some(MoveButton::Right), _) => {
+            Some(MoveButton::Right) => {
                 context.movement.run_right();
                 Response::Transition(State::run())
             }
-            (_, _) => {
+            _ => {
                 context.movement.stop_x();
                 Response::Transition(State::idle())
             }
@@ -649,16 +1146,16 @@ impl CharacterStateMachine {
 
     /// Checks inputs and updates velocity without changing state.
     fn handled_movement_input(inputs: &Inputs, context: &mut SMContext<'_>) -> Response<State> {
-        match (&inputs.0, &inputs.1) {
-            (Some(MoveButton::Left), _) => {
+        match &inputs.movement {
+            Some(MoveButton::Left) => {
                 context.movement.run_left();
                 Handled
             }
-            (Some(MoveButton::Right), _) => {
+            Some(MoveButton::Right) => {
                 context.movement.run_right();
                 Handled
             }
-            (_, _) => {
+            _ => {
                 context.movement.stop_x();
                 Handled
             }
@@ -669,16 +1166,31 @@ impl CharacterStateMachine {
         inputs: &Inputs,
         context: &mut SMContext<'_>,
     ) -> Result<Response<State>, ()> {
-        match (&inputs.0, &inputs.1, &inputs.2) {
-            (_, Some(ModifierButton::Attack), Some(ModifierButton::Jump) | None) => {
-                if context.timers.air_attack_anim.is_stopped()
+        match &inputs.movement {
+            Some(MoveButton::Down) if inputs.has(ModifierButton::Attack) => {
+                if context.timers.is_stopped(PlayerTimer::DownStrike)
+                    && let Ok(attack) =
+                        Offense::try_attack(PlayerAttacks::SimpleMelee, context.resources, 1)
+                {
+                    context
+                        .animations
+                        .play_once(DOWN_STRIKE_CLIP, context.movement.get_direction());
+                    context.down_hurtbox.set_attack(attack);
+                    context.down_hurtbox.set_enabled(true);
+                    context.timers.start(PlayerTimer::DownStrike);
+                }
+                Ok(Handled)
+            }
+            _ if inputs.has(ModifierButton::Attack) => {
+                if context.attack.is_none()
                     && let Ok(attack) =
                         Offense::try_attack(PlayerAttacks::SimpleMelee, context.resources, 1)
                 {
-                    let anim = format!("attack_{}", context.movement.get_direction());
-                    context.graphics.play_then_resume(&anim);
-                    context.hurtbox.bind_mut().set_attack(attack);
-                    context.timers.air_attack_anim.start();
+                    context
+                        .animations
+                        .play_once(AIR_ATTACK_CLIP, context.movement.get_direction());
+                    context.hurtbox.set_attack(attack);
+                    Self::start_attack(context.frame_data.air_attack, context);
                     Ok(Handled)
                 } else {
                     Ok(Handled)
@@ -688,9 +1200,37 @@ impl CharacterStateMachine {
         }
     }
 
+    fn handle_ledge_grab(inputs: &Inputs, context: &mut SMContext<'_>) -> Response<State> {
+        match &inputs.movement {
+            Some(MoveButton::Left) => {
+                Self::end_attack(context);
+                context.movement.ledge_hang(Direction::Left);
+                Response::Transition(State::ledge_hang())
+            }
+            Some(MoveButton::Right) => {
+                Self::end_attack(context);
+                context.movement.ledge_hang(Direction::Right);
+                Response::Transition(State::ledge_hang())
+            }
+            _ => Handled,
+        }
+    }
+
+    fn handle_ladder_grab(inputs: &Inputs, context: &mut SMContext<'_>) -> Response<State> {
+        match &inputs.movement {
+            Some(MoveButton::Up | MoveButton::Down) => {
+                Self::end_attack(context);
+                context.movement.climb(inputs.movement);
+                Response::Transition(State::climbing())
+            }
+            _ => Handled,
+        }
+    }
+
     fn handle_wall_grab(inputs: &Inputs, context: &mut SMContext<'_>) -> Response<State> {
-        match &inputs.0 {
-            Some(MoveButton::Left | MoveButton::Right) => {
+        match &inputs.movement {
+            Some(MoveButton::Left | MoveButton::Right) if context.abilities.wall_grab => {
+                Self::end_attack(context);
                 context.movement.wall_grab_velocity();
                 Response::Transition(State::wall_grab())
             }
@@ -698,3 +1238,983 @@ impl CharacterStateMachine {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use statig::prelude::*;
+
+    use crate::{
+        entities::{
+            combat::{
+                frame_data::{AttackWindow, PlayerFrameData},
+                offense::{Attack, Damage, Offense, Spell},
+                resources::{CombatResources, Heal, Health, Mana, Stamina},
+            },
+            graphics::AnimationSink,
+            hit_reg::AttackSlot,
+            movements::Direction,
+            player::{
+                abilities::MovementAbilities,
+                character_state_machine::{
+                    CharacterStateMachine, Event, SMContext, State, Timers, advance_attack,
+                },
+                physics::Movement,
+                time::{PlayerTimer, SMTimers},
+            },
+        },
+        utils::input_hanlder::{InputBuffer, Inputs, ModifierButton as Mb, MoveButton},
+    };
+
+    /// Timers that only stop when told to, or when the harness sends their timeout.
+    #[derive(Default)]
+    struct FakeTimers(HashSet<PlayerTimer>);
+
+    impl SMTimers for FakeTimers {
+        fn start(&mut self, timer: PlayerTimer) {
+            self.0.insert(timer);
+        }
+
+        fn stop(&mut self, timer: PlayerTimer) {
+            self.0.remove(&timer);
+        }
+
+        fn is_stopped(&self, timer: PlayerTimer) -> bool {
+            !self.0.contains(&timer)
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeHurtbox {
+        attack: Option<Attack>,
+        enabled: bool,
+    }
+
+    impl AttackSlot for FakeHurtbox {
+        fn set_attack(&mut self, attack: Attack) {
+            self.attack = Some(attack);
+        }
+
+        fn set_enabled(&mut self, enabled: bool) {
+            self.enabled = enabled;
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeAnimations(Vec<String>);
+
+    impl AnimationSink for FakeAnimations {
+        fn play_once(&mut self, clip: &'static str, dir: Direction) {
+            self.0.push(format!("{clip}_{dir}"));
+        }
+    }
+
+    /// Drives the player's state machine without a scene tree.
+    struct Harness {
+        sm: StateMachine<CharacterStateMachine>,
+        timers: FakeTimers,
+        resources: CombatResources,
+        hurtbox: FakeHurtbox,
+        down_hurtbox: FakeHurtbox,
+        off: Offense,
+        movement: Movement,
+        animations: FakeAnimations,
+        abilities: MovementAbilities,
+        buffer: InputBuffer,
+        low_ceiling: bool,
+        attack: Option<AttackWindow>,
+        frame_data: PlayerFrameData,
+        cast: Option<Spell>,
+    }
+
+    impl Harness {
+        fn new() -> Self {
+            Self {
+                sm: CharacterStateMachine.state_machine(),
+                timers: FakeTimers::default(),
+                resources: CombatResources::new(
+                    Health::new(50, 50, Heal::new(10, 2)),
+                    Stamina::new(50, 50),
+                    Mana::new(50, 50),
+                ),
+                hurtbox: FakeHurtbox::default(),
+                down_hurtbox: FakeHurtbox::default(),
+                off: Offense::new(Vec::new(), [None, None, None]),
+                movement: Movement::default(),
+                animations: FakeAnimations::default(),
+                abilities: MovementAbilities::default(),
+                buffer: InputBuffer::default(),
+                low_ceiling: false,
+                attack: None,
+                frame_data: PlayerFrameData::default(),
+                cast: None,
+            }
+        }
+
+        /// Handles `event`. Timeouts stop their timer first, like a one shot `Timer`, and
+        /// `AttackFinished` plays out the rest of the attack.
+        fn send(&mut self, event: &Event) -> State {
+            match event {
+                Event::TimerElapsed(timer, _) => self.timers.stop(player_timer(*timer)),
+                Event::AttackFinished(_) => while !self.advance() {},
+                _ => (),
+            }
+            let mut context = SMContext::new(
+                &mut self.timers,
+                &mut self.resources,
+                &mut self.hurtbox,
+                &mut self.down_hurtbox,
+                &self.off,
+                &mut self.movement,
+                &mut self.animations,
+                &self.abilities,
+                &mut self.buffer,
+                self.low_ceiling,
+                &mut self.attack,
+                &self.frame_data,
+            );
+            self.sm.handle_with_context(event, &mut context);
+            if let Some(spell) = context.take_cast() {
+                self.cast = Some(spell);
+            }
+            *self.sm.state()
+        }
+
+        /// Advances the attack by a frame, returning `true` once it's over.
+        fn advance(&mut self) -> bool {
+            advance_attack(&mut self.attack, &mut self.hurtbox)
+        }
+    }
+
+    fn player_timer(timer: Timers) -> PlayerTimer {
+        match timer {
+            Timers::DodgeAnimation => PlayerTimer::DodgeAnim,
+            Timers::HealingAnimation => PlayerTimer::HealingAnim,
+            Timers::HurtAnimation => PlayerTimer::HurtAnim,
+            Timers::ParryAnimation => PlayerTimer::ParryAnim,
+            Timers::JumpLimit => PlayerTimer::JumpLimit,
+            Timers::CastSpellAnimation => PlayerTimer::CastSpellAnim,
+            Timers::LedgeClimbAnimation => PlayerTimer::LedgeClimbAnim,
+            Timers::SlideAnimation => PlayerTimer::SlideAnim,
+            Timers::HardLandingAnimation => PlayerTimer::HardLandingAnim,
+        }
+    }
+
+    /// The timer whose timeout ends `state`, for states that only end on a timeout. Attack states
+    /// end with their attack instead.
+    fn ending_timer(state: State) -> Option<PlayerTimer> {
+        match state {
+            State::Dodging {} | State::AirDash {} => Some(PlayerTimer::DodgeAnim),
+            State::Hurt {} => Some(PlayerTimer::HurtAnim),
+            State::Healing {} => Some(PlayerTimer::HealingAnim),
+            State::Parry {} => Some(PlayerTimer::ParryAnim),
+            State::CastSpell {} => Some(PlayerTimer::CastSpellAnim),
+            State::LedgeClimb {} => Some(PlayerTimer::LedgeClimbAnim),
+            State::Slide {} => Some(PlayerTimer::SlideAnim),
+            State::HardLanding {} => Some(PlayerTimer::HardLandingAnim),
+            State::Dead {} => Some(PlayerTimer::DeathAnim),
+            _ => None,
+        }
+    }
+
+    fn input(movement: Option<MoveButton>, actions: &[Mb]) -> Event {
+        Event::InputChanged(Inputs::new(movement, actions))
+    }
+
+    fn finished(movement: Option<MoveButton>, actions: &[Mb]) -> Event {
+        Event::AttackFinished(Inputs::new(movement, actions))
+    }
+
+    fn elapsed(timer: Timers, movement: Option<MoveButton>, actions: &[Mb]) -> Event {
+        Event::TimerElapsed(timer, Inputs::new(movement, actions))
+    }
+
+    fn none() -> Inputs {
+        Inputs::default()
+    }
+
+    fn held(movement: MoveButton) -> Inputs {
+        Inputs::new(Some(movement), &[])
+    }
+
+    use MoveButton::{Down, Left, Right, Up};
+
+    struct Case {
+        name: &'static str,
+        setup: fn(&mut Harness),
+        events: Vec<Event>,
+        expected: State,
+    }
+
+    fn case(name: &'static str, events: Vec<Event>, expected: State) -> Case {
+        Case {
+            name,
+            setup: |_| (),
+            events,
+            expected,
+        }
+    }
+
+    fn case_with(
+        name: &'static str,
+        setup: fn(&mut Harness),
+        events: Vec<Event>,
+        expected: State,
+    ) -> Case {
+        Case {
+            name,
+            setup,
+            events,
+            expected,
+        }
+    }
+
+    /// Events that leave the player in the state named by each function, from idle.
+    fn jumping() -> Vec<Event> {
+        vec![input(None, &[Mb::Jump])]
+    }
+
+    fn falling() -> Vec<Event> {
+        vec![Event::FailedFloorCheck(none())]
+    }
+
+    fn attacking() -> Vec<Event> {
+        vec![input(None, &[Mb::Attack])]
+    }
+
+    fn wall_grab() -> Vec<Event> {
+        [falling(), vec![Event::GrabbedWall(held(Right))]].concat()
+    }
+
+    fn ledge_hang() -> Vec<Event> {
+        [falling(), vec![Event::GrabbedLedge(held(Right))]].concat()
+    }
+
+    fn climbing() -> Vec<Event> {
+        vec![Event::GrabbedLadder(held(Up))]
+    }
+
+    fn then(mut events: Vec<Event>, event: Event) -> Vec<Event> {
+        events.push(event);
+        events
+    }
+
+    fn damaged(h: &mut Harness) {
+        h.resources.take_damage(Damage(10));
+    }
+
+    fn wall_grab_unlocked(h: &mut Harness) {
+        h.abilities.wall_grab = true;
+    }
+
+    fn cases() -> Vec<Case> {
+        vec![
+            // Idle
+            case("idle runs", vec![input(Some(Right), &[])], State::Run {}),
+            case(
+                "idle crouches",
+                vec![input(Some(Down), &[])],
+                State::Crouch {},
+            ),
+            case("idle jumps", jumping(), State::Jumping {}),
+            case(
+                "idle dodges",
+                vec![input(None, &[Mb::Dodge])],
+                State::Dodging {},
+            ),
+            case(
+                "idle slides",
+                vec![input(Some(Down), &[Mb::Dodge])],
+                State::Slide {},
+            ),
+            case("idle attacks", attacking(), State::Attacking {}),
+            case(
+                "idle crouch attacks",
+                vec![input(Some(Down), &[Mb::Attack])],
+                State::CrouchAttack {},
+            ),
+            case(
+                "idle charged attacks",
+                vec![input(None, &[Mb::ChargedAttack])],
+                State::Chargedattack {},
+            ),
+            case(
+                "idle parries",
+                vec![input(None, &[Mb::Parry])],
+                State::Parry {},
+            ),
+            case(
+                "heal at full health",
+                vec![input(None, &[Mb::Heal])],
+                State::Idle {},
+            ),
+            case_with(
+                "heal when damaged",
+                damaged,
+                vec![input(None, &[Mb::Heal])],
+                State::Healing {},
+            ),
+            case(
+                "cast without a spell",
+                vec![input(None, &[Mb::CastSpell])],
+                State::Idle {},
+            ),
+            case_with(
+                "cast the selected spell",
+                |h| h.off = Offense::new(Vec::new(), [Some(Spell::TwinPillar), None, None]),
+                vec![input(None, &[Mb::CastSpell])],
+                State::CastSpell {},
+            ),
+            case("walk off a ledge", falling(), State::Falling {}),
+            case("grab a ladder", climbing(), State::Climbing {}),
+            case(
+                "ladder needs up or down",
+                vec![Event::GrabbedLadder(held(Right))],
+                State::Idle {},
+            ),
+            case(
+                "enter water",
+                vec![Event::EnteredWater(none())],
+                State::Swimming {},
+            ),
+            case("idle hurt", vec![Event::Hurt], State::Hurt {}),
+            case("idle dies", vec![Event::Died], State::Dead {}),
+            case(
+                "idle disabled",
+                vec![Event::ForceDisabled],
+                State::ForcedDisabled {},
+            ),
+            case_with(
+                "stand under a low ceiling",
+                |h| h.low_ceiling = true,
+                vec![input(None, &[])],
+                State::Crouch {},
+            ),
+            // Run
+            case(
+                "run stops",
+                vec![input(Some(Right), &[]), input(None, &[])],
+                State::Idle {},
+            ),
+            case(
+                "run dodges",
+                vec![input(Some(Right), &[]), input(Some(Right), &[Mb::Dodge])],
+                State::Dodging {},
+            ),
+            case(
+                "run hurt",
+                vec![input(Some(Right), &[]), Event::Hurt],
+                State::Hurt {},
+            ),
+            case(
+                "run off a ledge",
+                vec![
+                    input(Some(Right), &[]),
+                    Event::FailedFloorCheck(held(Right)),
+                ],
+                State::Falling {},
+            ),
+            // Dodging
+            case(
+                "dodge ends",
+                vec![
+                    input(None, &[Mb::Dodge]),
+                    elapsed(Timers::DodgeAnimation, None, &[]),
+                ],
+                State::Idle {},
+            ),
+            case(
+                "dodge during cooldown",
+                vec![
+                    input(None, &[Mb::Dodge]),
+                    elapsed(Timers::DodgeAnimation, None, &[]),
+                    input(None, &[Mb::Dodge]),
+                ],
+                State::Idle {},
+            ),
+            case(
+                "dodge ignores other timers",
+                vec![
+                    input(None, &[Mb::Dodge]),
+                    elapsed(Timers::HurtAnimation, None, &[]),
+                ],
+                State::Dodging {},
+            ),
+            case(
+                "dodge off a ledge",
+                vec![input(None, &[Mb::Dodge]), Event::FailedFloorCheck(none())],
+                State::Falling {},
+            ),
+            // Jumping
+            case(
+                "release jump early",
+                then(jumping(), input(None, &[Mb::ReleasedJump(0.1)])),
+                State::Falling {},
+            ),
+            case(
+                "jump limit",
+                then(jumping(), elapsed(Timers::JumpLimit, None, &[Mb::Jump])),
+                State::Falling {},
+            ),
+            case(
+                "jump lands",
+                then(jumping(), Event::Landed(none())),
+                State::Idle {},
+            ),
+            case(
+                "jump hits ceiling",
+                then(jumping(), Event::HitCeiling(none())),
+                State::Falling {},
+            ),
+            case(
+                "wall grab needs the ability",
+                then(jumping(), Event::GrabbedWall(held(Right))),
+                State::Jumping {},
+            ),
+            case_with(
+                "jump grabs wall",
+                wall_grab_unlocked,
+                then(jumping(), Event::GrabbedWall(held(Right))),
+                State::WallGrab {},
+            ),
+            case(
+                "jump grabs ledge",
+                then(jumping(), Event::GrabbedLedge(held(Right))),
+                State::LedgeHang {},
+            ),
+            case(
+                "air dash needs the ability",
+                then(jumping(), input(Some(Right), &[Mb::Jump, Mb::Dodge])),
+                State::Jumping {},
+            ),
+            case_with(
+                "jump air dashes",
+                |h| h.abilities.air_dash = true,
+                then(jumping(), input(Some(Right), &[Mb::Jump, Mb::Dodge])),
+                State::AirDash {},
+            ),
+            case(
+                "jump lands hard",
+                then(jumping(), Event::HardLanded),
+                State::HardLanding {},
+            ),
+            case(
+                "air attack, land, attack again",
+                [
+                    jumping(),
+                    vec![
+                        input(None, &[Mb::Jump, Mb::Attack]),
+                        Event::Landed(none()),
+                        input(None, &[Mb::Attack]),
+                    ],
+                ]
+                .concat(),
+                State::Attacking {},
+            ),
+            case(
+                "jump pogo bounces",
+                then(jumping(), Event::PogoBounced),
+                State::Falling {},
+            ),
+            // Falling
+            case(
+                "coyote jump",
+                then(falling(), input(None, &[Mb::Jump])),
+                State::Jumping {},
+            ),
+            case(
+                "no jump without coyote time or double jump",
+                [
+                    jumping(),
+                    vec![
+                        input(None, &[Mb::ReleasedJump(0.1)]),
+                        input(None, &[Mb::Jump]),
+                    ],
+                ]
+                .concat(),
+                State::Falling {},
+            ),
+            case_with(
+                "double jump once",
+                |h| h.abilities.double_jump = true,
+                [
+                    jumping(),
+                    vec![
+                        input(None, &[Mb::ReleasedJump(0.1)]),
+                        input(None, &[Mb::Jump]),
+                    ],
+                ]
+                .concat(),
+                State::Jumping {},
+            ),
+            case_with(
+                "no third jump",
+                |h| h.abilities.double_jump = true,
+                [
+                    jumping(),
+                    vec![
+                        input(None, &[Mb::ReleasedJump(0.1)]),
+                        input(None, &[Mb::Jump]),
+                        input(None, &[Mb::ReleasedJump(0.1)]),
+                        input(None, &[Mb::Jump]),
+                    ],
+                ]
+                .concat(),
+                State::Falling {},
+            ),
+            case(
+                "fall lands",
+                then(falling(), Event::Landed(none())),
+                State::Idle {},
+            ),
+            case(
+                "fall lands running",
+                then(falling(), Event::Landed(held(Right))),
+                State::Run {},
+            ),
+            case_with(
+                "buffered jump on landing",
+                |h| h.buffer.push(Mb::Jump),
+                then(falling(), Event::Landed(none())),
+                State::Jumping {},
+            ),
+            case(
+                "air attack stays airborne",
+                then(falling(), input(None, &[Mb::Attack])),
+                State::Falling {},
+            ),
+            case(
+                "fall grabs ladder",
+                then(falling(), Event::GrabbedLadder(held(Down))),
+                State::Climbing {},
+            ),
+            case(
+                "fall into water",
+                then(falling(), Event::EnteredWater(none())),
+                State::Swimming {},
+            ),
+            // Attacking
+            case(
+                "attack chains",
+                then(attacking(), finished(None, &[Mb::Attack])),
+                State::ChainAttack {},
+            ),
+            case_with(
+                "buffered attack chains",
+                |h| h.buffer.push(Mb::Attack),
+                then(attacking(), finished(None, &[])),
+                State::ChainAttack {},
+            ),
+            case_with(
+                "chain needs stamina",
+                |h| {
+                    h.resources = CombatResources::new(
+                        Health::new(50, 50, Heal::new(10, 2)),
+                        Stamina::new(5, 50),
+                        Mana::new(50, 50),
+                    )
+                },
+                then(attacking(), finished(None, &[Mb::Attack])),
+                State::Idle {},
+            ),
+            case(
+                "attack ends",
+                then(attacking(), finished(None, &[])),
+                State::Idle {},
+            ),
+            case(
+                "attack hurt",
+                then(attacking(), Event::Hurt),
+                State::Hurt {},
+            ),
+            case(
+                "chain attack ends",
+                [
+                    attacking(),
+                    vec![finished(None, &[Mb::Attack]), finished(Some(Left), &[])],
+                ]
+                .concat(),
+                State::Run {},
+            ),
+            case(
+                "chain attack hurt",
+                [
+                    attacking(),
+                    vec![finished(None, &[Mb::Attack]), Event::Hurt],
+                ]
+                .concat(),
+                State::Hurt {},
+            ),
+            // Charged attack
+            case(
+                "charged attack ends",
+                vec![input(None, &[Mb::ChargedAttack]), finished(None, &[])],
+                State::Idle {},
+            ),
+            case(
+                "charged attack isn't interrupted by hurt",
+                vec![input(None, &[Mb::ChargedAttack]), Event::Hurt],
+                State::Chargedattack {},
+            ),
+            // Hurt
+            case(
+                "hurt ends",
+                vec![Event::Hurt, elapsed(Timers::HurtAnimation, Some(Left), &[])],
+                State::Run {},
+            ),
+            case(
+                "hurt ignores input",
+                vec![Event::Hurt, input(None, &[Mb::Jump])],
+                State::Hurt {},
+            ),
+            // Healing
+            case_with(
+                "heal ends with a cooldown",
+                damaged,
+                vec![
+                    input(None, &[Mb::Heal]),
+                    elapsed(Timers::HealingAnimation, None, &[]),
+                    input(None, &[Mb::Heal]),
+                ],
+                State::Idle {},
+            ),
+            // Parry
+            case(
+                "parry ends",
+                vec![
+                    input(None, &[Mb::Parry]),
+                    elapsed(Timers::ParryAnimation, None, &[]),
+                ],
+                State::Idle {},
+            ),
+            // Forced disabled
+            case(
+                "disabled ignores input",
+                vec![Event::ForceDisabled, input(None, &[Mb::Jump])],
+                State::ForcedDisabled {},
+            ),
+            case(
+                "enabled again",
+                vec![Event::ForceDisabled, Event::ForceEnabled],
+                State::Idle {},
+            ),
+            // Wall grab
+            case_with(
+                "wall jump",
+                wall_grab_unlocked,
+                then(wall_grab(), input(Some(Left), &[Mb::Jump])),
+                State::Jumping {},
+            ),
+            case_with(
+                "wall jump during cooldown",
+                |h| {
+                    h.abilities.wall_grab = true;
+                    h.timers.start(PlayerTimer::WallJump);
+                },
+                then(wall_grab(), input(Some(Left), &[Mb::Jump])),
+                State::Falling {},
+            ),
+            case_with(
+                "hurt during wall grab",
+                wall_grab_unlocked,
+                then(wall_grab(), Event::Hurt),
+                State::Falling {},
+            ),
+            case_with(
+                "let go of wall",
+                wall_grab_unlocked,
+                then(wall_grab(), input(None, &[])),
+                State::Falling {},
+            ),
+            case_with(
+                "slide down wall to the floor",
+                wall_grab_unlocked,
+                then(wall_grab(), Event::Landed(none())),
+                State::Idle {},
+            ),
+            // Cast spell
+            case_with(
+                "cast ends",
+                |h| h.off = Offense::new(Vec::new(), [Some(Spell::TwinPillar), None, None]),
+                vec![
+                    input(None, &[Mb::CastSpell]),
+                    elapsed(Timers::CastSpellAnimation, None, &[]),
+                ],
+                State::Idle {},
+            ),
+            // Air dash
+            case_with(
+                "air dash ends",
+                |h| h.abilities.air_dash = true,
+                [
+                    jumping(),
+                    vec![
+                        input(Some(Right), &[Mb::Dodge]),
+                        elapsed(Timers::DodgeAnimation, None, &[]),
+                    ],
+                ]
+                .concat(),
+                State::Falling {},
+            ),
+            case_with(
+                "air dash lands",
+                |h| h.abilities.air_dash = true,
+                [
+                    jumping(),
+                    vec![input(Some(Right), &[Mb::Dodge]), Event::Landed(none())],
+                ]
+                .concat(),
+                State::Idle {},
+            ),
+            // Ledge hang
+            case(
+                "jump off ledge",
+                then(ledge_hang(), input(None, &[Mb::Jump])),
+                State::Jumping {},
+            ),
+            case(
+                "climb ledge",
+                then(ledge_hang(), input(Some(Right), &[])),
+                State::LedgeClimb {},
+            ),
+            case(
+                "drop from ledge",
+                then(ledge_hang(), input(Some(Down), &[])),
+                State::Falling {},
+            ),
+            case(
+                "hurt on ledge",
+                then(ledge_hang(), Event::Hurt),
+                State::Falling {},
+            ),
+            case(
+                "ledge climb ends",
+                [
+                    ledge_hang(),
+                    vec![
+                        input(Some(Right), &[]),
+                        elapsed(Timers::LedgeClimbAnimation, None, &[]),
+                    ],
+                ]
+                .concat(),
+                State::Idle {},
+            ),
+            // Crouch
+            case(
+                "stand up",
+                vec![input(Some(Down), &[]), input(None, &[])],
+                State::Idle {},
+            ),
+            case_with(
+                "stay crouched under a low ceiling",
+                |h| h.low_ceiling = true,
+                vec![input(Some(Down), &[]), input(Some(Right), &[])],
+                State::Crouch {},
+            ),
+            case(
+                "crouch attack ends crouched",
+                vec![input(Some(Down), &[Mb::Attack]), finished(Some(Down), &[])],
+                State::Crouch {},
+            ),
+            case_with(
+                "slide ends under a low ceiling",
+                |h| h.low_ceiling = true,
+                vec![
+                    input(Some(Down), &[Mb::Dodge]),
+                    elapsed(Timers::SlideAnimation, None, &[]),
+                ],
+                State::Crouch {},
+            ),
+            // Hard landing
+            case(
+                "hard landing ignores input",
+                then(then(falling(), Event::HardLanded), input(None, &[Mb::Jump])),
+                State::HardLanding {},
+            ),
+            case(
+                "hard landing ends",
+                then(
+                    then(falling(), Event::HardLanded),
+                    elapsed(Timers::HardLandingAnimation, None, &[]),
+                ),
+                State::Idle {},
+            ),
+            case(
+                "hard landing loses the floor",
+                then(
+                    then(falling(), Event::HardLanded),
+                    Event::FailedFloorCheck(none()),
+                ),
+                State::Falling {},
+            ),
+            case(
+                "hard landing drops into water",
+                then(
+                    then(falling(), Event::HardLanded),
+                    Event::EnteredWater(none()),
+                ),
+                State::Swimming {},
+            ),
+            // Climbing
+            case(
+                "jump off ladder",
+                then(climbing(), input(Some(Left), &[Mb::Jump])),
+                State::Jumping {},
+            ),
+            case(
+                "leave ladder",
+                then(climbing(), Event::LeftLadder(none())),
+                State::Falling {},
+            ),
+            case(
+                "climb to the floor",
+                then(climbing(), Event::Landed(none())),
+                State::Idle {},
+            ),
+            case(
+                "hurt on ladder",
+                then(climbing(), Event::Hurt),
+                State::Falling {},
+            ),
+            // Swimming
+            case(
+                "swim stroke",
+                vec![Event::EnteredWater(none()), input(None, &[Mb::Jump])],
+                State::Swimming {},
+            ),
+            case(
+                "jump out of water",
+                vec![
+                    Event::EnteredWater(none()),
+                    Event::LeftWater(Inputs::new(None, &[Mb::Jump])),
+                ],
+                State::Jumping {},
+            ),
+            case(
+                "fall out of water",
+                vec![Event::EnteredWater(none()), Event::LeftWater(none())],
+                State::Falling {},
+            ),
+            // Dead
+            case(
+                "dead ignores input",
+                vec![Event::Died, input(None, &[Mb::Jump]), Event::Hurt],
+                State::Dead {},
+            ),
+            case(
+                "respawn",
+                vec![Event::Died, Event::Respawned],
+                State::Idle {},
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_character_state_machine_transitions() {
+        for case in cases() {
+            let mut harness = Harness::new();
+            (case.setup)(&mut harness);
+            let mut state = State::Idle {};
+            for event in &case.events {
+                state = harness.send(event);
+            }
+            assert_eq!(state, case.expected, "{}", case.name);
+            // States that end on a timeout would never end if their timer wasn't started.
+            if let Some(timer) = ending_timer(state) {
+                assert!(
+                    !harness.timers.is_stopped(timer),
+                    "{}: {timer:?} isn't running",
+                    case.name
+                );
+            }
+            let attacking = matches!(
+                state,
+                State::Attacking {}
+                    | State::ChainAttack {}
+                    | State::Chargedattack {}
+                    | State::CrouchAttack {}
+            );
+            if attacking {
+                assert!(harness.attack.is_some(), "{}: no attack", case.name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_character_state_machine_side_effects() {
+        let mut harness = Harness::new();
+        harness.send(&Event::FailedFloorCheck(none()));
+        harness.timers.stop(PlayerTimer::Coyote);
+        harness.send(&input(Some(Down), &[Mb::Attack]));
+        assert!(harness.down_hurtbox.enabled);
+        assert!(harness.down_hurtbox.attack.is_some());
+        assert_eq!(harness.animations.0, ["down_strike_right"]);
+
+        let mut harness = Harness::new();
+        harness.off = Offense::new(Vec::new(), [Some(Spell::TwinPillar), None, None]);
+        harness.send(&input(None, &[Mb::CastSpell]));
+        assert!(matches!(harness.cast, Some(Spell::TwinPillar)));
+        assert_eq!(harness.resources.mana().amount(), 40);
+    }
+
+    #[test]
+    fn test_attack_frame_windows() {
+        let mut harness = Harness::new();
+        let data = harness.frame_data.attack;
+        harness.send(&input(None, &[Mb::Attack]));
+        for frame in 1..data.total() {
+            harness.advance();
+            let active = (data.startup..data.startup + data.active).contains(&frame);
+            assert_eq!(harness.hurtbox.enabled, active, "frame {frame}");
+            // Dodging is only accepted in the cancel window.
+            let state = harness.send(&input(None, &[Mb::Dodge]));
+            if frame < data.cancel_from {
+                assert_eq!(state, State::Attacking {}, "frame {frame}");
+                harness.send(&input(None, &[]));
+            } else {
+                assert_eq!(state, State::Dodging {}, "frame {frame}");
+                assert!(harness.attack.is_none());
+                assert!(!harness.hurtbox.enabled);
+                break;
+            }
+        }
+
+        // Attacking in the cancel window goes straight to the next combo step.
+        let mut harness = Harness::new();
+        harness.send(&input(None, &[Mb::Attack]));
+        harness.send(&input(None, &[]));
+        for _ in 0..data.cancel_from {
+            harness.advance();
+        }
+        let state = harness.send(&input(None, &[Mb::Attack]));
+        assert_eq!(state, State::ChainAttack {});
+        assert_eq!(
+            harness.attack,
+            Some(AttackWindow::new(harness.frame_data.chain_attack))
+        );
+
+        // Getting hurt mid swing disables the hurtbox.
+        let mut harness = Harness::new();
+        harness.send(&input(None, &[Mb::Attack]));
+        for _ in 0..data.startup {
+            harness.advance();
+        }
+        assert!(harness.hurtbox.enabled);
+        harness.send(&Event::Hurt);
+        assert!(harness.attack.is_none());
+        assert!(!harness.hurtbox.enabled);
+
+        // Landing or grabbing a wall ends an air attack.
+        for leave_air in [Event::Landed(none()), Event::GrabbedWall(held(Right))] {
+            let mut harness = Harness::new();
+            harness.abilities.wall_grab = true;
+            harness.send(&input(None, &[Mb::Jump]));
+            harness.send(&input(Some(Right), &[Mb::Jump, Mb::Attack]));
+            assert!(harness.attack.is_some());
+            harness.send(&leave_air);
+            assert!(harness.attack.is_none(), "{leave_air:?}");
+            assert!(!harness.hurtbox.enabled, "{leave_air:?}");
+        }
+    }
+}
@@ -1,8 +1,7 @@
 use godot::{classes::InputEvent, prelude::*};
 
 use crate::{
-    entities::entity_stats::{ModifierKind, Stat, StatModifier},
-    utils::global_data_singleton::GlobalData,
+    utils::{global_data_singleton::GlobalData, input_hanlder::Action},
     world::item::*,
 };
 
@@ -44,7 +43,7 @@ impl INode for ItemComponent {
     }
 
     fn unhandled_input(&mut self, input: Gd<InputEvent>) {
-        if input.is_action_pressed("interact") {
+        if input.is_action_pressed(Action::Interact.name()) {
             self.pickup_item();
         }
     }
@@ -64,15 +63,8 @@ impl INode for ItemComponent {
             "res://assets/icon.svg".to_string(),
         )));
 
-        let relic = Item::new(
-            ItemKind::Relic {
-                effect: StatModifier::new(Stat::MaxHealth, ModifierKind::Flat(2)),
-            },
-            "Relic Increase Max Health".to_string(),
-            Some("A relic which, when equipped, increases max health".to_string()),
-            "res://assets/bullet.webp".to_string(),
-        );
-        self.unlocked_relics.insert(0, Some(relic));
+        self.unlocked_relics
+            .insert(0, Item::from_id("max_health_relic"));
     }
 }
 
@@ -88,31 +80,39 @@ impl ItemComponent {
         self.item = None;
     }
 
+    /// Adds an item to the inventory, or unlocks its ability.
+    pub fn add_item(&mut self, item: Item) {
+        match item.kind {
+            ItemKind::Collectable => self.collectables.push(Some(item)),
+            ItemKind::RosaryBead { effect: _ } => {
+                if self.unlocked_beads.iter().flatten().any(|i| i == &item) {
+                } else if let Some(slot) = self.unlocked_beads.iter_mut().find(|i| i.is_none()) {
+                    *slot = Some(item);
+                }
+            }
+            ItemKind::Relic { effect: _ } => {
+                if self.unlocked_relics.iter().flatten().any(|i| i == &item) {
+                } else if let Some(relic) = self.unlocked_relics.iter_mut().find(|i| i.is_none()) {
+                    *relic = Some(item);
+                }
+            }
+            ItemKind::Ability { ability } => {
+                // Cloned so the player isn't bound while `GlobalData` is.
+                let player = GlobalData::singleton().bind().player.clone();
+                if let Some(mut player) = player {
+                    player.bind_mut().unlock_ability(ability);
+                }
+            }
+            _ => self.quest_and_other.push(Some(item)),
+        }
+    }
+
     pub fn pickup_item(&mut self) {
         if self.in_item_area {
             let item = self.item.clone();
             if let Some(mut item) = item {
                 let bind = item.bind().item.clone();
-                match bind.kind {
-                    ItemKind::Collectable => self.collectables.push(Some(bind)),
-                    ItemKind::RosaryBead { effect: _ } => {
-                        if self.unlocked_beads.iter().flatten().any(|i| i == &bind) {
-                        } else if let Some(item) =
-                            self.unlocked_beads.iter_mut().find(|i| i.is_none())
-                        {
-                            *item = Some(bind);
-                        }
-                    }
-                    ItemKind::Relic { effect: _ } => {
-                        if self.unlocked_relics.iter().flatten().any(|i| i == &bind) {
-                        } else if let Some(relic) =
-                            self.unlocked_relics.iter_mut().find(|i| i.is_none())
-                        {
-                            *relic = Some(bind);
-                        }
-                    }
-                    _ => self.quest_and_other.push(Some(bind)),
-                }
+                self.add_item(bind);
 
                 GlobalData::singleton()
                     .bind_mut()
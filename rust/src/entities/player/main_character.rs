@@ -1,5 +1,8 @@
 use godot::{
-    classes::{Area2D, CharacterBody2D, ICharacterBody2D, Input, RayCast2D},
+    classes::{
+        Area2D, CharacterBody2D, CollisionShape2D, Engine, ICharacterBody2D, Input, InputEvent,
+        RayCast2D,
+    },
     obj::WithBaseField,
     prelude::*,
 };
@@ -10,39 +13,112 @@ use crate::{
     entities::{
         combat::{
             defense::{Defense, Resistance},
-            offense::{Buff, Element, Offense, Spell},
-            resources::{CombatResources, Heal, Health, Mana, ResourceChanged, Stamina},
+            frame_data::{AttackFrameData, AttackWindow, PlayerFrameData},
+            offense::{Buff, Damage, Element, Offense, Spell},
+            resources::{
+                CombatResources, DamageSource, Heal, Health, Mana, ResourceChanged, Stamina,
+            },
         },
         enemies::projectile::Projectile,
         entity_stats::{EntityStats, Stat, StatModifier, StatVal},
         graphics::Graphics,
         hit_reg::{self, Hitbox, Hurtbox},
+        movement_tuning::MovementTuning,
         movements::Direction,
         player::{
+            abilities::{MovementAbilities, MovementAbility},
             character_state_machine::{self as csm, Timers},
             item_component::ItemComponent,
-            physics::{FloorState, StateInfo, WallCastCollision, WallState},
+            physics::{
+                FloorState, LandingImpact, StateInfo, WallCastCollision, WallState, WaterProperties,
+            },
             shaky_player_camera::{PlayerCamera, TraumaLevel},
             time::PlayerTimers,
         },
     },
     utils::{
+        collision_layers::CollisionLayers,
         global_data_singleton::GlobalData,
-        input_hanlder::{DevInputHandler, InputHandler, Inputs},
+        input_device::InputDevice,
+        input_hanlder::{
+            Action, Deadzone, DevInputHandler, InputHandler, InputState, Inputs, ModifierButton,
+            MoveButton,
+        },
+        input_replay::{self, Desync, InputRecording, InputReplay},
         node_utils::ResetTimer,
+        sm_history::{self, EntityHistory, TransitionHistory},
+        time_control::{self, HITSTOP_FRAMES, PERFECT_PARRY_SLOW_MOTION},
     },
 };
 
 type State = csm::State;
 type Event = csm::Event;
 
+/// Areas in this group bounce the player when hit with a down strike, e.g. `world/hazard.tscn`.
+/// They need the `WorldEffects` layer for the down strike to see them.
+pub const HAZARD_GROUP: &str = "hazards";
+
+/// How far the player is moved when climbing over a ledge to the right.
+const LEDGE_CLIMB_OFFSET: Vector2 = Vector2::new(16.0, -48.0);
+
+/// How fast the player flies while noclip is on.
+const NOCLIP_SPEED: f32 = 400.0;
+
 #[derive(GodotClass)]
 #[class(init, base=CharacterBody2D)]
 pub struct MainCharacter {
+    /// How long after walking off a ledge the player can still jump.
+    #[export]
+    #[init(val = 0.15)]
+    coyote_time: f64,
+    /// How long before landing a jump press is remembered.
+    #[export]
+    #[init(val = 0.15)]
+    jump_buffer_time: f64,
+    /// Stick tilt below this is ignored.
+    #[export]
+    #[init(val = 0.2)]
+    stick_deadzone: f32,
+    /// Stick tilt above this counts as fully tilted.
+    #[export]
+    #[init(val = 0.9)]
+    stick_saturation: f32,
+    /// Abilities the player has before picking up any item.
+    #[export_group(name = "Starting Abilities")]
+    #[export]
+    start_with_double_jump: bool,
+    #[export]
+    #[init(val = true)]
+    start_with_air_dash: bool,
+    #[export]
+    #[init(val = true)]
+    start_with_wall_grab: bool,
     inputs: Inputs,
+    pub input_state: InputState,
+    /// Records or replays the player's input for reproducing bugs.
+    pub replay: InputReplay,
+    /// Ignores all damage. Toggled from the dev console.
+    pub god_mode: bool,
+    /// Flies through walls instead of running the state machine. Toggled from the dev console.
+    noclip: bool,
     previous_state: State,
     pub state: StateMachine<csm::CharacterStateMachine>,
+    /// Recent state transitions, for debugging.
+    history: TransitionHistory,
     pub stats: EntityStats,
+    /// Movement abilities unlocked by picking up their items.
+    pub abilities: MovementAbilities,
+    /// Horizontal position of the ladder the player is in front of.
+    ladder: Option<f32>,
+    /// Whether up or down has been held since the player last climbed. Jumping or getting hurt
+    /// off a ladder only grabs one again once they're released.
+    climb_held: bool,
+    /// Damage taken each time the player runs out of breath in the current water.
+    drowning_damage: Option<Damage>,
+    /// The attack in progress, advanced every physics frame.
+    attack: Option<AttackWindow>,
+    #[init(val = PlayerFrameData::default())]
+    frame_data: PlayerFrameData,
     base: Base<CharacterBody2D>,
 
     #[init(val = OnReady::manual())]
@@ -56,10 +132,25 @@ pub struct MainCharacter {
         )
         }))]
     hit_reg: OnReady<hit_reg::HitReg>,
+    /// Hurtbox below the player used by the down strike.
+    #[init(node = "DownHurtbox")]
+    down_hurtbox: OnReady<Gd<Hurtbox>>,
     #[init(node = "LeftWallCast")]
     left_wall_cast: OnReady<Gd<RayCast2D>>,
     #[init(node = "RightWallCast")]
     right_wall_cast: OnReady<Gd<RayCast2D>>,
+    #[init(node = "PlatformCast")]
+    platform_cast: OnReady<Gd<RayCast2D>>,
+    #[init(node = "CeilingCast")]
+    ceiling_cast: OnReady<Gd<RayCast2D>>,
+    #[init(node = "PhysicsShape")]
+    standing_shape: OnReady<Gd<CollisionShape2D>>,
+    #[init(node = "CrouchShape")]
+    crouch_shape: OnReady<Gd<CollisionShape2D>>,
+    #[init(node = "LeftLedgeCast")]
+    left_ledge_cast: OnReady<Gd<RayCast2D>>,
+    #[init(node = "RightLedgeCast")]
+    right_ledge_cast: OnReady<Gd<RayCast2D>>,
     #[init(node = "ItemComponent")]
     pub item_comp: OnReady<Gd<ItemComponent>>,
     #[init(val = OnReady::from_base_fn(|this|{ Graphics::new(this)}))]
@@ -74,29 +165,40 @@ pub struct MainCharacter {
     #[init(val = Defense::new(vec![Resistance::Physical(5), Resistance::Elemental(Element::Fire, 10)]))]
     pub def: Defense,
     #[init(val = CombatResources::new(
-        Health::new(30, 30, Heal::new(5)), Stamina::new(30, 50), Mana::new(50, 50)))]
+        Health::new(30, 30, Heal::new(5, 3)), Stamina::new(30, 50), Mana::new(50, 50)))]
     pub resources: CombatResources,
 }
 
 #[godot_api]
 impl ICharacterBody2D for MainCharacter {
     fn ready(&mut self) {
-        self.movements.speeds = physics::Speeds {
-            running: 180.0,
-            jumping: 450.0,
-            dodging: 800.0,
+        self.on_movement_tuning_reloaded(MovementTuning::load_or_default());
+        self.frame_data = AttackFrameData::load_or_default();
+        self.abilities = MovementAbilities {
+            double_jump: self.start_with_double_jump,
+            air_dash: self.start_with_air_dash,
+            wall_grab: self.start_with_wall_grab,
         };
 
-        self.timer.init(PlayerTimers::new(
-            &self.to_gd().upcast(),
-            &mut self.graphics,
-        ));
+        let _ = self.graphics.validate::<csm::State>("MainCharacter", &[]);
+        self.timer
+            .init(PlayerTimers::new(&self.to_gd().upcast(), &self.graphics));
+        self.timer.coyote.set_wait_time(self.coyote_time);
+        self.reset_input_state();
+        Input::singleton()
+            .signals()
+            .joy_connection_changed()
+            .connect(InputDevice::on_joy_connection_changed);
 
         let hitbox = self.base().get_node_as::<Hitbox>("Hitbox");
         hitbox
             .signals()
             .area_entered()
             .connect_other(&self.to_gd(), Self::on_area_entered_hitbox);
+        self.down_hurtbox
+            .signals()
+            .area_entered()
+            .connect_other(&self.to_gd(), Self::on_down_strike_hit);
 
         let this = self.to_gd();
         GlobalData::singleton()
@@ -111,6 +213,12 @@ impl ICharacterBody2D for MainCharacter {
             .modifier_removed()
             .connect_other(&this, Self::on_modifier_removed);
 
+        GlobalData::singleton()
+            .bind_mut()
+            .sig_handler()
+            .movement_tuning_reloaded()
+            .connect_other(&this, Self::on_movement_tuning_reloaded);
+
         self.stats.add_slice(&[
             (Stat::Health, StatVal::new(50)),
             (Stat::MaxHealth, StatVal::new(50)),
@@ -119,24 +227,71 @@ impl ICharacterBody2D for MainCharacter {
             (Stat::Level, StatVal::new(1)),
         ]);
 
+        self.base_mut()
+            .set_collision_mask_value(CollisionLayers::PlatformLedges as i32, true);
+
+        // Ride moving one-way platforms as well as solid ones.
+        let floor_layers = self.base().get_platform_floor_layers()
+            | 1 << (CollisionLayers::PlatformLedges as u32 - 1);
+        self.base_mut().set_platform_floor_layers(floor_layers);
+
         self.init_timers();
         self.previous_state = State::Idle {};
+
+        let mut data = GlobalData::singleton();
+        let commands = &mut data.bind_mut().console_commands;
+        commands.register(
+            "record",
+            "record",
+            "Starts or stops recording input",
+            0,
+            0,
+            |_| Self::console_player(|p| p.toggle_recording()),
+        );
+        commands.register(
+            "replay",
+            "replay",
+            "Replays the last input recording",
+            0,
+            0,
+            |_| Self::console_player(|p| p.start_replay()),
+        );
+        commands.register(
+            "dump_history",
+            "dump_history",
+            "Writes recent state transitions to user://sm_history.log",
+            0,
+            0,
+            |_| Self::console_player(|p| p.dump_sm_history()),
+        );
+    }
+
+    fn input(&mut self, event: Gd<InputEvent>) {
+        if let Some(device) = InputDevice::from_event(&event) {
+            InputDevice::set_active(device);
+        }
     }
 
     fn physics_process(&mut self, delta: f32) {
+        if time_control::is_frozen(&self.to_gd().upcast()) {
+            return;
+        }
+        // Catches up on clips held back by hitstop.
+        self.graphics
+            .update(self.state.state(), self.movements.get_direction());
+        if let State::Dead {} = self.state.state() {
+            self.process_dead(delta);
+            return;
+        }
+        if self.noclip {
+            self.process_noclip(delta);
+            return;
+        }
+        let (frame, delta) = self.replay.begin_frame(&Input::singleton(), delta);
+
         let tick = self.resources.tick_resources(delta);
         if let Ok(tick) = tick {
-            match tick {
-                ResourceChanged::Stamina { previous, new } => {
-                    self.signals().stamina_changed().emit(previous, new);
-                }
-                ResourceChanged::Mana { previous, new } => {
-                    self.signals().mana_changed().emit(previous, new);
-                }
-                ResourceChanged::Health { previous, new } => {
-                    self.signals().player_health_changed().emit(previous, new);
-                }
-            }
+            self.emit_resource_changed(tick);
         }
         let floor_state =
             FloorState::from_something(self.base().is_on_floor(), self.base().is_on_floor_only());
@@ -145,12 +300,23 @@ impl ICharacterBody2D for MainCharacter {
             self.left_wall_cast.is_colliding(),
             self.right_wall_cast.is_colliding(),
         );
+        let ledge_collision = WallCastCollision::from_something(
+            self.left_ledge_cast.is_colliding(),
+            self.right_ledge_cast.is_colliding(),
+        );
         let wall_state =
             WallState::from_something(self.base().is_on_wall(), self.base().is_on_wall_only());
+        self.input_state.update(&frame, delta);
+        self.try_rotate_spell();
         let input = DevInputHandler::handle_unhandled(&Input::singleton(), self);
+        if csm::advance_attack(&mut self.attack, &mut self.hit_reg.hurtbox) {
+            self.transition_sm(&Event::AttackFinished(input));
+        }
 
         if self.inputs != input {
             self.inputs = input;
+            self.movements.set_tilt(input.tilt);
+            self.try_drop_through(&input);
             self.transition_sm(&Event::InputChanged(input));
         }
         if self.movements.not_on_floor(floor_state) && self.state.state() != (&State::Jumping {}) {
@@ -158,16 +324,24 @@ impl ICharacterBody2D for MainCharacter {
         }
         if self.movements.landed(floor_state, state_info) {
             self.timer.jump_limit.reset();
-            self.transition_sm(&Event::Landed(input));
+            self.on_landed(&input);
         }
-        if let Some(wall_state) = wall_state
+        if self
+            .movements
+            .ledge_grab(state_info, &input, wall_collision, ledge_collision)
+        {
+            self.transition_sm(&Event::GrabbedLedge(input));
+        } else if let Some(wall_state) = wall_state
             && physics::Movement::wall_grab(state_info, wall_state, &input, wall_collision)
         {
             self.transition_sm(&Event::GrabbedWall(input));
         }
+        self.check_ladder(&input, floor_state, state_info);
+        self.check_water(&input);
         if !matches!(self.state.state(), &State::WallGrab {} | &State::AirDash {}) {
             self.movements.apply_gravity(state_info, delta);
         }
+        self.update_collision_shape();
         let v = self.movements.velocity();
         self.update_camera(v);
         self.base_mut().set_velocity(v);
@@ -175,6 +349,14 @@ impl ICharacterBody2D for MainCharacter {
         if physics::hit_ceiling(&mut self.to_gd(), &mut self.movements) {
             self.transition_sm(&Event::HitCeiling(input));
         }
+        let checksum = input_replay::checksum(
+            self.base().get_global_position(),
+            &format!("{:?}", self.state.state()),
+        );
+        if let Err(Desync { frame }) = self.replay.end_frame(frame, delta, checksum) {
+            let dump = self.dump_sm_history().unwrap_or_else(|e| e);
+            godot_warn!("Replay desynced from the recording at frame {frame}. {dump}");
+        }
     }
 }
 
@@ -189,7 +371,14 @@ impl MainCharacter {
     #[signal]
     pub fn mana_changed(previous: i64, new: i64);
 
+    /// Emitted once the death animation has finished.
+    #[signal]
+    pub fn died();
+
     fn on_area_entered_hitbox(&mut self, area: Gd<Area2D>) {
+        if self.god_mode || matches!(self.state.state(), State::Dead {}) {
+            return;
+        }
         let hurtbox = area.cast::<Hurtbox>();
         let attack = hurtbox.bind().attack.clone().unwrap();
         if attack.is_parryable() && self.parried() {
@@ -202,71 +391,151 @@ impl MainCharacter {
             let damage = self.def.apply_resistances(&attack);
             let res = self.resources.take_damage(damage);
             self.signals().player_health_changed().emit(res.0, res.1);
-            if self.resources.health().is_dead() {
-                self.on_death();
-            }
             self.camera
                 .bind_mut()
                 .add_trauma(TraumaLevel::from(damage.0));
-            self.transition_sm(&Event::Hurt);
+            let mut frozen = vec![self.to_gd().upcast()];
+            frozen.extend(hurtbox.get_owner());
+            time_control::hitstop(&frozen, HITSTOP_FRAMES);
+            if self.resources.health().is_dead() {
+                self.on_death();
+            } else {
+                self.transition_sm(&Event::Hurt);
+            }
         }
     }
 
     fn on_parry_timeout(&mut self) {
-        let input = InputHandler::handle(&Input::singleton(), self);
+        let input = InputHandler::handle(&self.input_state);
         self.transition_sm(&Event::TimerElapsed(Timers::ParryAnimation, input));
     }
 
-    // TODO: Chain attacking.
-    fn on_attack_timeout(&mut self) {
-        let input = InputHandler::handle(&Input::singleton(), self);
-        self.transition_sm(&Event::TimerElapsed(Timers::AttackAnimation, input));
-    }
-
-    fn on_attack_2_timeout(&mut self) {
-        let input = InputHandler::handle(&Input::singleton(), self);
-        self.transition_sm(&Event::TimerElapsed(Timers::Attack2Animation, input));
-    }
-
     fn on_healing_timeout(&mut self) {
         let change = self.resources.heal();
         self.signals()
             .player_health_changed()
             .emit(change.0, change.1);
-        let input = InputHandler::handle(&Input::singleton(), self);
+        let input = InputHandler::handle(&self.input_state);
         self.transition_sm(&Event::TimerElapsed(Timers::HealingAnimation, input));
     }
 
     fn on_dodge_animation_timeout(&mut self) {
         self.timer.dodge_cooldown.start();
-        let input = InputHandler::handle(&Input::singleton(), self);
+        let input = InputHandler::handle(&self.input_state);
         self.transition_sm(&Event::TimerElapsed(Timers::DodgeAnimation, input));
     }
 
     fn on_hurt_animation_timeout(&mut self) {
-        let input = InputHandler::handle(&Input::singleton(), self);
+        let input = InputHandler::handle(&self.input_state);
         self.transition_sm(&Event::TimerElapsed(Timers::HurtAnimation, input));
     }
 
     fn on_jump_limit_timeout(&mut self) {
-        let input = InputHandler::handle(&Input::singleton(), self);
+        let input = InputHandler::handle(&self.input_state);
         self.transition_sm(&Event::TimerElapsed(Timers::JumpLimit, input));
     }
 
-    fn on_charged_att_anim_timeout(&mut self) {
-        let input = InputHandler::handle(&Input::singleton(), self);
-        self.transition_sm(&Event::TimerElapsed(Timers::ChargedAttack, input));
-    }
-
     fn on_cast_spell_anim_timeout(&mut self) {
-        let input = InputHandler::handle(&Input::singleton(), self);
+        let input = InputHandler::handle(&self.input_state);
         self.transition_sm(&Event::TimerElapsed(Timers::CastSpellAnimation, input));
     }
 
+    /// Moves the player on top of the ledge they climbed.
+    fn on_ledge_climb_anim_timeout(&mut self) {
+        let offset = match self.movements.get_direction() {
+            Direction::Left => Vector2::new(-LEDGE_CLIMB_OFFSET.x, LEDGE_CLIMB_OFFSET.y),
+            Direction::Right => LEDGE_CLIMB_OFFSET,
+        };
+        let position = self.base().get_global_position();
+        self.base_mut().set_global_position(position + offset);
+        let input = InputHandler::handle(&self.input_state);
+        self.transition_sm(&Event::TimerElapsed(Timers::LedgeClimbAnimation, input));
+    }
+
+    fn on_slide_anim_timeout(&mut self) {
+        let input = InputHandler::handle(&self.input_state);
+        self.transition_sm(&Event::TimerElapsed(Timers::SlideAnimation, input));
+    }
+
+    fn on_drop_through_timeout(&mut self) {
+        self.base_mut()
+            .set_collision_mask_value(CollisionLayers::PlatformLedges as i32, true);
+    }
+
+    /// The player ran out of breath and takes drowning damage until they leave the water.
+    fn on_breath_timeout(&mut self) {
+        let Some(damage) = self.drowning_damage else {
+            return;
+        };
+        self.take_environmental_damage(damage, DamageSource::Drowning);
+        if self.resources.health().is_dead() {
+            self.timer.breath.stop();
+        }
+    }
+
+    /// Bounces the player when the down strike hits an enemy, a projectile or a hazard.
+    fn on_down_strike_hit(&mut self, area: Gd<Area2D>) {
+        let bounce = area.clone().try_cast::<Hitbox>().is_ok()
+            || area.clone().try_cast::<Hurtbox>().is_ok()
+            || area.is_in_group(HAZARD_GROUP);
+        if bounce && !self.timer.down_strike.is_stopped() {
+            self.timer.down_strike.stop();
+            self.down_hurtbox.bind_mut().set_enabled(false);
+            self.transition_sm(&Event::PogoBounced);
+        }
+    }
+
+    fn on_down_strike_timeout(&mut self) {
+        self.down_hurtbox.bind_mut().set_enabled(false);
+    }
+
+    fn on_hard_landing_anim_timeout(&mut self) {
+        let input = InputHandler::handle(&self.input_state);
+        self.transition_sm(&Event::TimerElapsed(Timers::HardLandingAnimation, input));
+    }
+
+    /// Applies fall damage and the hard landing stun depending on how far the player fell.
+    fn on_landed(&mut self, input: &Inputs) {
+        match self.movements.landing_impact() {
+            LandingImpact::Soft => (),
+            LandingImpact::Hard => self.transition_sm(&Event::HardLanded),
+            LandingImpact::Damaging(damage) => {
+                self.take_environmental_damage(damage, DamageSource::Fall);
+                if self.resources.health().is_dead() {
+                    return;
+                }
+                self.transition_sm(&Event::HardLanded);
+            }
+        }
+        if !matches!(self.state.state(), State::HardLanding {}) {
+            self.transition_sm(&Event::Landed(*input));
+        }
+    }
+
+    /// Takes damage that isn't from an attack, ignoring resistances and parrying.
+    fn take_environmental_damage(&mut self, damage: Damage, source: DamageSource) {
+        if self.god_mode {
+            return;
+        }
+        let res = self.resources.take_damage_from(damage, source);
+        self.signals().player_health_changed().emit(res.0, res.1);
+        self.camera
+            .bind_mut()
+            .add_trauma(TraumaLevel::from(damage.0));
+        if self.resources.health().is_dead() {
+            self.on_death();
+        }
+    }
+
+    fn on_death_anim_timeout(&mut self) {
+        self.signals().died().emit();
+    }
+
     fn parried(&mut self) -> bool {
         if let State::Parry {} = self.state.state() {
             if self.timer.perfect_parry.get_time_left() > 0.0 {
                 println!("Perfect parry");
+                time_control::slow_motion(PERFECT_PARRY_SLOW_MOTION);
                 true
             } else if self.timer.parry.get_time_left() > 0.0 {
                 println!("Normal parry");
@@ -280,17 +549,33 @@ impl MainCharacter {
     }
 
     pub fn transition_sm(&mut self, event: &Event) {
+        let mut hurtbox = self.hit_reg.hurtbox.clone();
+        let mut down_hurtbox = (*self.down_hurtbox).clone();
         let mut context = csm::SMContext::new(
-            &mut self.timer,
+            &mut *self.timer,
             &mut self.resources,
-            self.hit_reg.hurtbox.clone(),
+            &mut hurtbox,
+            &mut down_hurtbox,
             &self.off,
             &mut self.movements,
-            &mut self.graphics,
+            &mut *self.graphics,
+            &self.abilities,
+            &mut self.input_state.buffer,
+            self.ceiling_cast.is_colliding(),
+            &mut self.attack,
+            &self.frame_data,
         );
+        let from = *self.state.state();
         self.state.handle_with_context(event, &mut context);
-        self.graphics
-            .update(self.state.state(), self.movements.get_direction());
+        if let Some(spell) = context.take_cast() {
+            self.base_mut().add_sibling(&spell.init_scene());
+        }
+        let frame = Engine::singleton().get_physics_frames();
+        self.history.record(frame, &from, event, self.state.state());
+        if !time_control::is_frozen(&self.to_gd().upcast()) {
+            self.graphics
+                .update(self.state.state(), self.movements.get_direction());
+        }
     }
 
     /// Sets timer lengths, timer callbacks, and adds timers as children of the player.
@@ -307,39 +592,146 @@ impl MainCharacter {
             },
             {
                 let mut this = this.clone();
-                move || this.bind_mut().on_attack_timeout()
+                move || this.bind_mut().on_healing_timeout()
             },
             {
                 let mut this = this.clone();
-                move || this.bind_mut().on_attack_2_timeout()
+                move || this.bind_mut().on_hurt_animation_timeout()
             },
             {
                 let mut this = this.clone();
-                move || this.bind_mut().on_healing_timeout()
+                move || this.bind_mut().on_parry_timeout()
             },
             {
                 let mut this = this.clone();
-                move || this.bind_mut().on_hurt_animation_timeout()
+                move || this.bind_mut().on_jump_limit_timeout()
             },
             {
                 let mut this = this.clone();
-                move || this.bind_mut().on_parry_timeout()
+                move || this.bind_mut().on_cast_spell_anim_timeout()
             },
             {
                 let mut this = this.clone();
-                move || this.bind_mut().on_jump_limit_timeout()
+                move || this.bind_mut().on_death_anim_timeout()
             },
             {
                 let mut this = this.clone();
-                move || this.bind_mut().on_charged_att_anim_timeout()
+                move || this.bind_mut().on_ledge_climb_anim_timeout()
             },
             {
                 let mut this = this.clone();
-                move || this.bind_mut().on_cast_spell_anim_timeout()
+                move || this.bind_mut().on_slide_anim_timeout()
+            },
+            {
+                let mut this = this.clone();
+                move || this.bind_mut().on_drop_through_timeout()
+            },
+            {
+                let mut this = this.clone();
+                move || this.bind_mut().on_breath_timeout()
+            },
+            {
+                let mut this = this.clone();
+                move || this.bind_mut().on_down_strike_timeout()
+            },
+            {
+                let mut this = this.clone();
+                move || this.bind_mut().on_hard_landing_anim_timeout()
             },
         );
     }
 
+    pub fn enter_ladder(&mut self, x: f32) {
+        self.ladder = Some(x);
+    }
+
+    pub fn exit_ladder(&mut self) {
+        self.ladder = None;
+    }
+
+    /// Starts swimming. `drowning` is the time the player can stay submerged and the damage taken
+    /// after.
+    pub fn enter_water(&mut self, water: WaterProperties, drowning: Option<(f64, Damage)>) {
+        self.movements.water = Some(water);
+        self.drowning_damage = drowning.map(|(_, damage)| damage);
+        if let Some((time, _)) = drowning {
+            self.timer.breath.set_wait_time(time);
+            self.timer.breath.start();
+        }
+    }
+
+    pub fn exit_water(&mut self) {
+        self.movements.water = None;
+        self.drowning_damage = None;
+        self.timer.breath.stop();
+    }
+
+    /// Grabs or lets go of a ladder. Pressing down only grabs a ladder while airborne, so
+    /// crouching on top of a ladder doesn't climb it.
+    fn check_ladder(&mut self, input: &Inputs, floor: FloorState, state_info: StateInfo) {
+        let climbing = matches!(self.state.state(), State::Climbing {});
+        if climbing {
+            self.climb_held = true;
+        } else if !matches!(input.movement, Some(MoveButton::Up | MoveButton::Down)) {
+            self.climb_held = false;
+        }
+        match self.ladder {
+            Some(x) if !climbing && !self.climb_held => {
+                let grab = match input.movement {
+                    Some(MoveButton::Up) => true,
+                    Some(MoveButton::Down) => floor == FloorState::NotOnFloor,
+                    _ => false,
+                };
+                if grab {
+                    self.transition_sm(&Event::GrabbedLadder(*input));
+                    if let State::Climbing {} = self.state.state() {
+                        let y = self.base().get_global_position().y;
+                        self.base_mut().set_global_position(Vector2::new(x, y));
+                    }
+                }
+            }
+            Some(_) if self.movements.climbed_down(floor, state_info) => {
+                self.transition_sm(&Event::Landed(*input));
+            }
+            None if climbing => self.transition_sm(&Event::LeftLadder(*input)),
+            _ => (),
+        }
+    }
+
+    fn check_water(&mut self, input: &Inputs) {
+        let swimming = matches!(self.state.state(), State::Swimming {});
+        if self.movements.water.is_some() && !swimming {
+            self.transition_sm(&Event::EnteredWater(*input));
+        } else if self.movements.water.is_none() && swimming {
+            self.transition_sm(&Event::LeftWater(*input));
+        }
+    }
+
+    /// Drops down through a one-way platform by briefly ignoring the platform layer.
+    fn try_drop_through(&mut self, input: &Inputs) {
+        if input.movement == Some(MoveButton::Down)
+            && input.has(ModifierButton::Jump)
+            && self.base().is_on_floor()
+            && self.platform_cast.is_colliding()
+        {
+            self.base_mut()
+                .set_collision_mask_value(CollisionLayers::PlatformLedges as i32, false);
+            self.timer.drop_through.start();
+        }
+    }
+
+    /// Swaps to the shorter collision shape while crouching or sliding.
+    fn update_collision_shape(&mut self) {
+        let crouched = matches!(
+            self.state.state(),
+            State::Crouch {} | State::CrouchAttack {} | State::Slide {}
+        );
+        if self.crouch_shape.is_disabled() == crouched {
+            self.standing_shape.set_disabled(crouched);
+            self.crouch_shape.set_disabled(!crouched);
+        }
+    }
+
     fn update_camera(&mut self, previous_velocity: Vector2) {
         if previous_velocity != self.movements.velocity() {
             if self.movements.velocity().x > 5.0 {
@@ -350,6 +742,13 @@ impl MainCharacter {
         }
     }
 
+    fn on_movement_tuning_reloaded(&mut self, tuning: Gd<MovementTuning>) {
+        let tuning = tuning.bind();
+        self.movements.speeds = tuning.player_speeds();
+        self.movements.gravity = tuning.player_gravity();
+        self.movements.landing = tuning.player_landing();
+    }
+
     fn on_new_modifier(&mut self, modifier: Gd<StatModifier>) {
         let modif = modifier.bind();
         self.stats.get_mut(modif.stat).apply_modifier(*modif);
@@ -360,6 +759,147 @@ impl MainCharacter {
         self.stats.get_mut(modif.stat).remove_modifier(*modif);
     }
 
+    /// Starts recording input, or stops and saves the current recording.
+    pub fn toggle_recording(&mut self) -> Result<String, String> {
+        match std::mem::take(&mut self.replay) {
+            InputReplay::Recording(recording) => {
+                recording
+                    .save()
+                    .map_err(|_| "Failed to save input recording".to_string())?;
+                Ok(format!("Saved {} frames of input", recording.frames.len()))
+            }
+            _ => {
+                let seed = godot::global::randi();
+                godot::global::seed(seed);
+                self.reset_input_state();
+                self.replay = InputReplay::Recording(InputRecording::new(
+                    seed,
+                    self.base().get_global_position(),
+                    format!("{:?}", self.state.state()),
+                ));
+                Ok("Recording input".to_string())
+            }
+        }
+    }
+
+    /// Replays the saved recording from where it started, in place of live input. Refuses to
+    /// start unless the player is in the state the recording started in, as the rest of the
+    /// state machine can't be restored and the replay would desync.
+    pub fn start_replay(&mut self) -> Result<String, String> {
+        let recording =
+            InputRecording::load().ok_or_else(|| "No input recording to replay".to_string())?;
+        let state = format!("{:?}", self.state.state());
+        if state != recording.start_state {
+            return Err(format!(
+                "Replay recorded in state {} but the player is {state}, not replaying",
+                recording.start_state
+            ));
+        }
+        godot::global::seed(recording.seed);
+        self.base_mut()
+            .set_global_position(recording.start_position);
+        self.movements.stop_x();
+        self.movements.stop_y();
+        self.reset_input_state();
+        let message = format!("Replaying {} frames of input", recording.frames.len());
+        self.replay = InputReplay::Replaying {
+            recording,
+            frame: 0,
+            desynced: false,
+        };
+        Ok(message)
+    }
+
+    /// The player's state and recent transitions, for the debug overlay.
+    pub fn sm_history(&self) -> EntityHistory {
+        EntityHistory {
+            name: self.base().get_name().to_string(),
+            state: format!("{:?}", self.state.state()),
+            history: self.history.clone(),
+        }
+    }
+
+    /// Writes the transition history of the player and every enemy in the map to `user://`.
+    pub fn dump_sm_history(&mut self) -> Result<String, String> {
+        let mut histories = vec![self.sm_history()];
+        histories.extend(sm_history::enemy_histories(&self.to_gd().upcast()));
+        sm_history::dump(&histories)
+            .map(|()| format!("Dumped state history to {}", sm_history::HISTORY_PATH))
+            .map_err(|()| "Failed to dump state history".to_string())
+    }
+
+    /// Selects the next or previous hot spell when its action was just pressed.
+    fn try_rotate_spell(&mut self) {
+        let step = if self
+            .input_state
+            .is_just_pressed(Action::RotateAbilitiesLeft)
+        {
+            -1
+        } else if self
+            .input_state
+            .is_just_pressed(Action::RotateAbilitiesRight)
+        {
+            1
+        } else {
+            return;
+        };
+        let slot = self.off.rotate_spell(step);
+        GlobalData::singleton()
+            .bind_mut()
+            .sig_handler()
+            .selected_spell_changed()
+            .emit(slot as i64);
+    }
+
+    /// Forgets held and buffered input, so recordings and replays start from the same state.
+    fn reset_input_state(&mut self) {
+        self.input_state = InputState::default();
+        self.input_state
+            .buffer
+            .set_window(ModifierButton::Jump, self.jump_buffer_time);
+        self.input_state.deadzone = Deadzone {
+            inner: self.stick_deadzone,
+            outer: self.stick_saturation,
+        };
+    }
+
+    /// Runs `f` on the player for a console command.
+    fn console_player(f: fn(&mut Self) -> Result<String, String>) -> Result<String, String> {
+        let mut player = GlobalData::singleton()
+            .bind()
+            .player
+            .clone()
+            .ok_or_else(|| "No player".to_string())?;
+        f(&mut player.bind_mut())
+    }
+
+    pub fn is_noclip(&self) -> bool {
+        self.noclip
+    }
+
+    /// Turns noclip on or off. The player is moved without collision while it's on, and falls
+    /// normally again once it's off.
+    pub fn set_noclip(&mut self, enabled: bool) {
+        self.noclip = enabled;
+        self.movements.stop_x();
+        self.movements.stop_y();
+        self.base_mut().set_velocity(Vector2::ZERO);
+        if !enabled {
+            self.transition_sm(&Event::FailedFloorCheck(Inputs::default()));
+        }
+    }
+
+    fn process_noclip(&mut self, delta: f32) {
+        let direction = Input::singleton().get_vector(
+            Action::West.name(),
+            Action::East.name(),
+            Action::North.name(),
+            Action::South.name(),
+        );
+        let position = self.base().get_global_position() + direction * NOCLIP_SPEED * delta;
+        self.base_mut().set_global_position(position);
+    }
+
     /// Transitions state machine from it's current state to `disabled`.
     /// Effectively disables input handling.
     pub fn force_disabled(&mut self) {
@@ -375,7 +915,61 @@ impl MainCharacter {
         self.movements.get_direction()
     }
 
+    pub fn unlock_ability(&mut self, ability: MovementAbility) {
+        self.abilities.unlock(ability);
+    }
+
+    /// Restores all combat resources, notifying any listeners of the changes.
+    pub fn rest(&mut self) {
+        for change in self.resources.refill() {
+            self.emit_resource_changed(change);
+        }
+    }
+
+    fn emit_resource_changed(&mut self, change: ResourceChanged) {
+        match change {
+            ResourceChanged::Stamina { previous, new } => {
+                self.signals().stamina_changed().emit(previous, new);
+            }
+            ResourceChanged::Mana { previous, new } => {
+                self.signals().mana_changed().emit(previous, new);
+            }
+            ResourceChanged::Health { previous, new } => {
+                self.signals().player_health_changed().emit(previous, new);
+            }
+        }
+    }
+
+    /// Brings the player back to life with full resources. The caller is responsible for
+    /// positioning the player.
+    pub fn respawn(&mut self) {
+        self.rest();
+        self.movements.stop_x();
+        self.movements.stop_y();
+        self.hit_reg
+            .hitbox
+            .set_deferred("monitorable", &true.to_variant());
+        self.transition_sm(&Event::Respawned);
+    }
+
     fn on_death(&mut self) {
-        self.base_mut().queue_free();
+        // Prevents enemies from detecting the player while dead.
+        self.hit_reg
+            .hitbox
+            .set_deferred("monitorable", &false.to_variant());
+        self.transition_sm(&Event::Died);
+    }
+
+    /// The player only falls to the ground while dead.
+    fn process_dead(&mut self, delta: f32) {
+        if self.base().is_on_floor() {
+            self.movements.stop_y();
+        } else {
+            let state_info = StateInfo::new(self.previous_state, *self.state.state());
+            self.movements.apply_gravity(state_info, delta);
+        }
+        let v = self.movements.velocity();
+        self.base_mut().set_velocity(v);
+        self.base_mut().move_and_slide();
     }
 }
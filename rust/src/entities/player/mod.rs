@@ -2,7 +2,7 @@ pub mod abilities;
 pub mod character_state_machine;
 pub mod item_component;
 pub mod main_character;
-mod physics;
+pub mod physics;
 pub mod shaky_player_camera;
 pub mod time;
 
@@ -5,7 +5,9 @@ use godot::{
 };
 
 use crate::{
-    entities::{movements::Direction, player::character_state_machine::State},
+    entities::{
+        combat::offense::Damage, movements::Direction, player::character_state_machine::State,
+    },
     utils::input_hanlder::{self, Inputs},
 };
 
@@ -34,6 +36,97 @@ pub struct Speeds {
     pub running: f32,
     pub jumping: f32,
     pub dodging: f32,
+    pub climbing: f32,
+    pub sliding: f32,
+    /// Fraction of `jumping` used when bouncing off of a down strike.
+    pub pogo_bounce_scale: f32,
+}
+
+/// How water affects movement, see `WaterVolume`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WaterProperties {
+    /// Multiplier applied to gravity while submerged.
+    pub gravity_scale: f32,
+    /// Multiplier applied to running and jumping speeds while submerged.
+    pub speed_scale: f32,
+    /// Upward acceleration pushing against gravity.
+    pub buoyancy: f32,
+    /// The fastest the entity can sink or rise.
+    pub max_vertical_speed: f32,
+}
+
+/// Gravity and fall speeds, see `MovementTuning`.
+#[derive(Clone, Copy)]
+pub struct Gravity {
+    pub gravity: f32,
+    pub terminal_velocity: f32,
+    pub held_jump_time: f32,
+    pub min_early_release: f32,
+    pub held_jump_fall_speed: f32,
+    pub early_release_fall_speed: f32,
+    pub short_jump_fall_speed: f32,
+}
+
+impl Default for Gravity {
+    fn default() -> Self {
+        Self {
+            gravity: 900.0,
+            terminal_velocity: 1300.0,
+            held_jump_time: 0.5,
+            min_early_release: 0.08,
+            held_jump_fall_speed: 300.0,
+            early_release_fall_speed: 350.0,
+            short_jump_fall_speed: 450.0,
+        }
+    }
+}
+
+/// Fall heights that cause a hard landing or fall damage, see `MovementTuning`.
+#[derive(Clone, Copy, Debug)]
+pub struct LandingThresholds {
+    pub hard_landing_height: f32,
+    /// Should be greater than `hard_landing_height`.
+    pub fall_damage_height: f32,
+    pub fall_damage: i64,
+    /// `fall_damage` is dealt again for every step fallen past `fall_damage_height`. Steps of 0
+    /// or less deal `fall_damage` once.
+    pub fall_damage_step: f32,
+}
+
+impl Default for LandingThresholds {
+    fn default() -> Self {
+        Self {
+            hard_landing_height: 256.0,
+            fall_damage_height: 480.0,
+            fall_damage: 5,
+            fall_damage_step: 64.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LandingImpact {
+    Soft,
+    Hard,
+    Damaging(Damage),
+}
+
+impl LandingThresholds {
+    pub fn impact(&self, fall_distance: f32) -> LandingImpact {
+        if fall_distance >= self.fall_damage_height {
+            let steps = if self.fall_damage_step > 0.0 {
+                ((fall_distance - self.fall_damage_height) / self.fall_damage_step) as i64
+            } else {
+                0
+            };
+            let damage = self.fall_damage.saturating_mul(steps.saturating_add(1));
+            LandingImpact::Damaging(Damage(damage))
+        } else if fall_distance >= self.hard_landing_height {
+            LandingImpact::Hard
+        } else {
+            LandingImpact::Soft
+        }
+    }
 }
 
 #[derive(Default, Clone, Copy)]
@@ -41,15 +134,38 @@ pub struct Movement {
     velocity: Vector2,
     early_gravity: f32,
     direction: Direction,
+    /// Whether jump was released since the last jump. Prevents a held jump from also double
+    /// jumping.
+    jump_released: bool,
+    double_jumped: bool,
+    /// How far an analog stick is tilted, from 0 to 1. `None` runs at full speed.
+    tilt: Option<f32>,
     pub speeds: Speeds,
+    pub gravity: Gravity,
+    /// The water the entity is submerged in, if any.
+    pub water: Option<WaterProperties>,
+    pub landing: LandingThresholds,
+    /// Distance fallen since the entity last moved upwards or held onto something.
+    fall_distance: f32,
+    /// Distance fallen before the most recent landing.
+    last_fall_distance: f32,
 }
 
 impl Movement {
+    pub fn set_tilt(&mut self, tilt: Option<f32>) {
+        self.tilt = tilt;
+    }
+
+    /// Running speed, scaled down by a partly tilted stick.
+    fn run_speed(&self) -> f32 {
+        self.speeds.running * self.tilt.unwrap_or(1.0)
+    }
+
     pub fn run_right(&mut self) {
-        self.velocity.x = self.speeds.running * Vector2::RIGHT.x;
+        self.velocity.x = self.run_speed() * Vector2::RIGHT.x;
     }
     pub fn run_left(&mut self) {
-        self.velocity.x = self.speeds.running * Vector2::LEFT.x;
+        self.velocity.x = self.run_speed() * Vector2::LEFT.x;
     }
     pub fn dodge_right(&mut self) {
         self.velocity.x = self.speeds.dodging * Vector2::RIGHT.x;
@@ -57,6 +173,42 @@ impl Movement {
     pub fn dodge_left(&mut self) {
         self.velocity.x = self.speeds.dodging * Vector2::LEFT.x;
     }
+    pub fn slide(&mut self, direction: Direction) {
+        match direction {
+            Direction::Right => self.velocity.x = self.speeds.sliding * Vector2::RIGHT.x,
+            Direction::Left => self.velocity.x = self.speeds.sliding * Vector2::LEFT.x,
+        }
+    }
+
+    /// Moves along a ladder. Up and down climb, anything else holds on.
+    pub fn climb(&mut self, input: Option<input_hanlder::MoveButton>) {
+        self.fall_distance = 0.0;
+        self.velocity.x = 0.0;
+        self.velocity.y = match input {
+            Some(input_hanlder::MoveButton::Up) => Vector2::UP.y * self.speeds.climbing,
+            Some(input_hanlder::MoveButton::Down) => Vector2::DOWN.y * self.speeds.climbing,
+            _ => 0.0,
+        };
+        self.early_gravity = 0.0;
+        self.double_jumped = false;
+    }
+
+    pub fn swim(&mut self, direction: Option<Direction>) {
+        let scale = self.water.map_or(1.0, |w| w.speed_scale);
+        self.velocity.x = match direction {
+            Some(Direction::Right) => self.run_speed() * scale,
+            Some(Direction::Left) => -self.run_speed() * scale,
+            None => 0.0,
+        };
+    }
+
+    /// Swims upwards.
+    pub fn swim_stroke(&mut self) {
+        let scale = self.water.map_or(1.0, |w| w.speed_scale);
+        self.velocity.y = Vector2::UP.y * self.speeds.jumping * scale;
+        self.double_jumped = false;
+    }
+
     pub fn stop_x(&mut self) {
         self.velocity.x = 0.0;
     }
@@ -74,26 +226,53 @@ impl Movement {
         self.dodge_left();
     }
 
+    /// Bounces upwards after a down strike connects, restoring the double jump.
+    pub fn pogo_bounce(&mut self) {
+        self.velocity.y = Vector2::UP.y * self.speeds.jumping * self.speeds.pogo_bounce_scale;
+        self.early_gravity = 0.0;
+        self.jump_released = true;
+        self.double_jumped = false;
+    }
+
     pub fn wall_grab_velocity(&mut self) {
+        self.fall_distance = 0.0;
+        self.double_jumped = false;
         self.stop_x();
         self.velocity.y = 30.0;
     }
 
     pub fn jump(&mut self) {
+        self.jump_released = false;
         self.velocity.x = 0.0;
         self.velocity.y = Vector2::UP.y * self.speeds.jumping;
     }
 
     pub fn jump_left(&mut self) {
-        self.velocity.x = self.speeds.running * Vector2::LEFT.x;
+        self.jump_released = false;
+        self.velocity.x = self.run_speed() * Vector2::LEFT.x;
         self.velocity.y = self.speeds.jumping * Vector2::UP.y;
     }
 
     pub fn jump_right(&mut self) {
-        self.velocity.x = self.speeds.running * Vector2::RIGHT.x;
+        self.jump_released = false;
+        self.velocity.x = self.run_speed() * Vector2::RIGHT.x;
         self.velocity.y = self.speeds.jumping * Vector2::UP.y;
     }
 
+    pub fn release_jump(&mut self) {
+        self.jump_released = true;
+    }
+
+    pub fn can_double_jump(&self) -> bool {
+        self.jump_released && !self.double_jumped
+    }
+
+    /// Uses up the double jump until the entity lands or grabs a wall.
+    pub fn double_jump(&mut self) {
+        self.double_jumped = true;
+        self.early_gravity = 0.0;
+    }
+
     pub fn get_direction(&mut self) -> Direction {
         let cur = self.direction;
         if self.velocity.x == 0.0 {
@@ -116,14 +295,30 @@ impl Movement {
     }
 
     pub fn apply_gravity(&mut self, state: StateInfo, delta: f32) {
-        const GRAVITY: f32 = 900.0;
-        const TERMINAL_VELOCITY: f32 = 1300.0;
+        if self.velocity.y > 0.0 && !matches!(state.current, State::Swimming {}) {
+            self.fall_distance += self.velocity.y * delta;
+        } else {
+            self.fall_distance = 0.0;
+        }
 
         match state.current {
-            State::Jumping {} | State::Falling {} if self.velocity.y < TERMINAL_VELOCITY => {
-                self.velocity.y += GRAVITY * delta
+            State::Jumping {} | State::Falling {} | State::Dead {}
+                if self.velocity.y < self.gravity.terminal_velocity =>
+            {
+                self.velocity.y += self.gravity.gravity * delta
             }
             State::Jumping {} => self.early_gravity += delta,
+            State::Swimming {} => {
+                let water = self.water.unwrap_or(WaterProperties {
+                    gravity_scale: 1.0,
+                    speed_scale: 1.0,
+                    buoyancy: 0.0,
+                    max_vertical_speed: self.gravity.terminal_velocity,
+                });
+                let acceleration = self.gravity.gravity * water.gravity_scale - water.buoyancy;
+                self.velocity.y = (self.velocity.y + acceleration * delta)
+                    .clamp(-water.max_vertical_speed, water.max_vertical_speed);
+            }
             _ => (),
         }
 
@@ -139,12 +334,15 @@ impl Movement {
 
     // Jump was released early, apply more gravity.
     pub fn apply_early_gravity(&mut self, time: f32) {
-        if time > 0.5 {
-            self.velocity.y = 300.0;
-        } else if self.early_gravity < 0.5 && self.early_gravity >= 0.08 {
-            self.velocity.y = 350.0;
+        self.jump_released = true;
+        let g = self.gravity;
+        if time > g.held_jump_time {
+            self.velocity.y = g.held_jump_fall_speed;
+        } else if self.early_gravity < g.held_jump_time && self.early_gravity >= g.min_early_release
+        {
+            self.velocity.y = g.early_release_fall_speed;
         } else {
-            self.velocity.y = 450.0;
+            self.velocity.y = g.short_jump_fall_speed;
         }
     }
 
@@ -153,14 +351,23 @@ impl Movement {
     pub fn landed(&mut self, floor: FloorState, state: StateInfo) -> bool {
         match floor {
             FloorState::OnlyOnFloor | FloorState::Both if is_airborne(state) => {
+                self.last_fall_distance = self.fall_distance;
+                self.fall_distance = 0.0;
                 self.velocity.y = 0.0;
                 self.early_gravity = 0.0;
+                self.jump_released = true;
+                self.double_jumped = false;
                 true
             }
             _ => false,
         }
     }
 
+    /// How hard the most recent landing was.
+    pub fn landing_impact(&self) -> LandingImpact {
+        self.landing.impact(self.last_fall_distance)
+    }
+
     pub fn wall_grab(
         state: StateInfo,
         wall: WallState,
@@ -169,7 +376,7 @@ impl Movement {
     ) -> bool {
         match wall {
             WallState::OnWallOnly if !matches!(state.current, State::WallGrab {}) => {
-                match input.0 {
+                match input.movement {
                     Some(input_hanlder::MoveButton::Left) => {
                         wallcast.is_some_and(|v| WallCastCollision::Left == v)
                     }
@@ -183,6 +390,52 @@ impl Movement {
         }
     }
 
+    /// Checks whether the entity reached the top of a wall while holding towards it. A ledge is
+    /// found when the wall cast collides and the ledge cast above it does not.
+    pub fn ledge_grab(
+        &self,
+        state: StateInfo,
+        input: &Inputs,
+        wallcast: Option<WallCastCollision>,
+        ledgecast: Option<WallCastCollision>,
+    ) -> bool {
+        let grabbable = match state.current {
+            State::Falling {} | State::WallGrab {} => true,
+            State::Jumping {} => self.velocity.y >= 0.0,
+            _ => false,
+        };
+        if !grabbable {
+            return false;
+        }
+        matches!(
+            (input.movement, wallcast, ledgecast),
+            (
+                Some(input_hanlder::MoveButton::Left),
+                Some(WallCastCollision::Left | WallCastCollision::Both),
+                None | Some(WallCastCollision::Right),
+            ) | (
+                Some(input_hanlder::MoveButton::Right),
+                Some(WallCastCollision::Right | WallCastCollision::Both),
+                None | Some(WallCastCollision::Left),
+            )
+        )
+    }
+
+    /// Holds the entity in place, facing the ledge.
+    pub fn ledge_hang(&mut self, ledge: Direction) {
+        self.fall_distance = 0.0;
+        self.velocity = Vector2::ZERO;
+        self.direction = ledge;
+        self.double_jumped = false;
+    }
+
+    /// Checks if the entity climbed down a ladder onto the floor.
+    pub fn climbed_down(&self, floor: FloorState, state: StateInfo) -> bool {
+        matches!(state.current, State::Climbing {})
+            && floor != FloorState::NotOnFloor
+            && self.velocity.y > 0.0
+    }
+
     pub fn not_on_floor(&self, floor: FloorState) -> bool {
         matches!(floor, FloorState::NotOnFloor if self.velocity.y.is_sign_positive())
         // !frame.on_floor && self.velocity.y.is_sign_positive()
@@ -255,3 +508,40 @@ impl StateInfo {
         Self { previous, current }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{LandingImpact, LandingThresholds};
+    use crate::entities::combat::offense::Damage;
+
+    #[test]
+    fn test_landing_impact() {
+        let thresholds = LandingThresholds {
+            hard_landing_height: 100.0,
+            fall_damage_height: 200.0,
+            fall_damage: 5,
+            fall_damage_step: 50.0,
+        };
+        assert_eq!(thresholds.impact(99.0), LandingImpact::Soft);
+        assert_eq!(thresholds.impact(150.0), LandingImpact::Hard);
+        assert_eq!(thresholds.impact(200.0), LandingImpact::Damaging(Damage(5)));
+        assert_eq!(
+            thresholds.impact(310.0),
+            LandingImpact::Damaging(Damage(15))
+        );
+
+        let unscaled = LandingThresholds {
+            fall_damage_step: 0.0,
+            ..thresholds
+        };
+        assert_eq!(unscaled.impact(310.0), LandingImpact::Damaging(Damage(5)));
+        let tiny = LandingThresholds {
+            fall_damage_step: f32::MIN_POSITIVE,
+            ..thresholds
+        };
+        assert_eq!(
+            tiny.impact(310.0),
+            LandingImpact::Damaging(Damage(i64::MAX))
+        );
+    }
+}
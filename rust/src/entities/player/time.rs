@@ -1,16 +1,27 @@
 use godot::{
     classes::{Node, Timer},
+    global::godot_warn,
     obj::Gd,
 };
 
 use crate::entities::graphics::Graphics;
 
+/// Timers that last as long as an animation, with the clip they're timed by.
+const ANIMATION_TIMERS: &[(PlayerTimer, &str)] = &[
+    (PlayerTimer::DodgeAnim, "dodge"),
+    (PlayerTimer::HealingAnim, "heal"),
+    (PlayerTimer::ParryAnim, "parry"),
+    (PlayerTimer::HurtAnim, "hurt"),
+    (PlayerTimer::DeathAnim, "death"),
+    (PlayerTimer::LedgeClimbAnim, "ledge_climb"),
+    (PlayerTimer::SlideAnim, "slide"),
+    (PlayerTimer::HardLandingAnim, "hard_landing"),
+];
+
 #[derive(Clone)]
 pub struct PlayerTimers {
     pub wall_jump: Gd<Timer>,
     pub dodge_anim: Gd<Timer>,
-    pub attack_anim: Gd<Timer>,
-    pub attack_2_anim: Gd<Timer>,
     pub healing_anim: Gd<Timer>,
     pub healing_cooldown: Gd<Timer>,
     pub hurt_anim: Gd<Timer>,
@@ -18,24 +29,52 @@ pub struct PlayerTimers {
     pub parry: Gd<Timer>,
     pub perfect_parry: Gd<Timer>,
     pub coyote: Gd<Timer>,
+    pub drop_through: Gd<Timer>,
+    /// Time the player can stay underwater before drowning.
+    pub breath: Gd<Timer>,
     pub dodge_cooldown: Gd<Timer>,
     pub jump_limit: Gd<Timer>,
-    pub charged_attack_anim: Gd<Timer>,
     pub spell_cooldown: Gd<Timer>,
     pub cast_spell_anim: Gd<Timer>,
-    pub air_attack_anim: Gd<Timer>,
+    /// How long the down strike's hurtbox stays active.
+    pub down_strike: Gd<Timer>,
+    pub death_anim: Gd<Timer>,
+    pub ledge_climb_anim: Gd<Timer>,
+    pub slide_anim: Gd<Timer>,
+    pub hard_landing_anim: Gd<Timer>,
 }
 
 impl PlayerTimers {
-    pub fn new(player: &Gd<Node>, graphics: &mut Graphics) -> Self {
+    fn get(&self, timer: PlayerTimer) -> &Gd<Timer> {
+        match timer {
+            PlayerTimer::WallJump => &self.wall_jump,
+            PlayerTimer::DodgeAnim => &self.dodge_anim,
+            PlayerTimer::HealingAnim => &self.healing_anim,
+            PlayerTimer::HealingCooldown => &self.healing_cooldown,
+            PlayerTimer::HurtAnim => &self.hurt_anim,
+            PlayerTimer::ParryAnim => &self.parry_anim,
+            PlayerTimer::Parry => &self.parry,
+            PlayerTimer::PerfectParry => &self.perfect_parry,
+            PlayerTimer::Coyote => &self.coyote,
+            PlayerTimer::DodgeCooldown => &self.dodge_cooldown,
+            PlayerTimer::JumpLimit => &self.jump_limit,
+            PlayerTimer::SpellCooldown => &self.spell_cooldown,
+            PlayerTimer::CastSpellAnim => &self.cast_spell_anim,
+            PlayerTimer::DownStrike => &self.down_strike,
+            PlayerTimer::DeathAnim => &self.death_anim,
+            PlayerTimer::LedgeClimbAnim => &self.ledge_climb_anim,
+            PlayerTimer::SlideAnim => &self.slide_anim,
+            PlayerTimer::HardLandingAnim => &self.hard_landing_anim,
+        }
+    }
+
+    pub fn new(player: &Gd<Node>, graphics: &Graphics) -> Self {
         fn get(node: &Gd<Node>, s: &str) -> Gd<Timer> {
             node.get_node_as::<Timer>(s)
         }
-        let mut this = Self {
+        let this = Self {
             wall_jump: get(player, "WallJump"),
             dodge_anim: get(player, "DodgeAnimation"),
-            attack_anim: get(player, "AttackAnimation"),
-            attack_2_anim: get(player, "AttackAnimation2"),
             healing_anim: get(player, "HealingAnimation"),
             healing_cooldown: get(player, "HealingCooldown"),
             hurt_anim: get(player, "HurtAnimation"),
@@ -43,45 +82,47 @@ impl PlayerTimers {
             parry: get(player, "Parry"),
             perfect_parry: get(player, "PerfectParry"),
             coyote: get(player, "Coyote"),
+            drop_through: get(player, "DropThrough"),
+            breath: get(player, "Breath"),
             dodge_cooldown: get(player, "DodgeCooldown"),
             jump_limit: get(player, "JumpLimit"),
-            charged_attack_anim: get(player, "ChargedAttack"),
             spell_cooldown: get(player, "SpellCooldown"),
             cast_spell_anim: get(player, "CastSpellAnimation"),
-            air_attack_anim: get(player, "AirAttackAnimation"),
+            down_strike: get(player, "DownStrike"),
+            death_anim: get(player, "DeathAnimation"),
+            ledge_climb_anim: get(player, "LedgeClimbAnimation"),
+            slide_anim: get(player, "SlideAnimation"),
+            hard_landing_anim: get(player, "HardLandingAnimation"),
         };
-        this.dodge_anim
-            .set_wait_time(graphics.get_animation_length("dodge_right"));
-        this.attack_anim
-            .set_wait_time(graphics.get_animation_length("attack_right"));
-        this.attack_2_anim
-            .set_wait_time(graphics.get_animation_length("chainattack_right"));
-        this.healing_anim
-            .set_wait_time(graphics.get_animation_length("heal_right"));
-        this.parry_anim
-            .set_wait_time(graphics.get_animation_length("parry_right"));
-        this.hurt_anim
-            .set_wait_time(graphics.get_animation_length("hurt_right"));
-        this.charged_attack_anim
-            .set_wait_time(graphics.get_animation_length("chargedattack_right"));
-        this.air_attack_anim
-            .set_wait_time(graphics.get_animation_length("airattack_right"));
+        for (timer, clip) in ANIMATION_TIMERS {
+            let name = format!("{clip}_right");
+            match graphics.get_animation_length(&name) {
+                Some(length) => this.get(*timer).clone().set_wait_time(length),
+                None => godot_warn!(
+                    "PlayerTimers: No animation `{name}` for {timer:?}, keeping the scene's wait time."
+                ),
+            }
+        }
         this
     }
 
     #[allow(clippy::too_many_arguments)]
-    pub fn connect_signals<A, B, C, D, E, F, G, H, I, J>(
+    pub fn connect_signals<A, B, C, D, E, F, G, H, I, J, K, L, M, N>(
         &mut self,
         on_walljump: A,
         on_dodge_anim: B,
-        on_attack_anim: C,
-        on_attack_2_anim: D,
-        on_healing_anim: E,
-        on_hurt_anim: F,
-        on_parry_anim: G,
-        on_jump_limit: H,
-        on_charged_attack_anim: I,
-        on_cast_spell_anim: J,
+        on_healing_anim: C,
+        on_hurt_anim: D,
+        on_parry_anim: E,
+        on_jump_limit: F,
+        on_cast_spell_anim: G,
+        on_death_anim: H,
+        on_ledge_climb_anim: I,
+        on_slide_anim: J,
+        on_drop_through: K,
+        on_breath: L,
+        on_down_strike: M,
+        on_hard_landing_anim: N,
     ) where
         A: FnMut() + 'static,
         B: FnMut() + 'static,
@@ -93,14 +134,13 @@ impl PlayerTimers {
         H: FnMut() + 'static,
         I: FnMut() + 'static,
         J: FnMut() + 'static,
+        K: FnMut() + 'static,
+        L: FnMut() + 'static,
+        M: FnMut() + 'static,
+        N: FnMut() + 'static,
     {
         self.wall_jump.signals().timeout().connect(on_walljump);
         self.dodge_anim.signals().timeout().connect(on_dodge_anim);
-        self.attack_anim.signals().timeout().connect(on_attack_anim);
-        self.attack_2_anim
-            .signals()
-            .timeout()
-            .connect(on_attack_2_anim);
         self.healing_anim
             .signals()
             .timeout()
@@ -108,13 +148,69 @@ impl PlayerTimers {
         self.hurt_anim.signals().timeout().connect(on_hurt_anim);
         self.parry_anim.signals().timeout().connect(on_parry_anim);
         self.jump_limit.signals().timeout().connect(on_jump_limit);
-        self.charged_attack_anim
-            .signals()
-            .timeout()
-            .connect(on_charged_attack_anim);
         self.cast_spell_anim
             .signals()
             .timeout()
             .connect(on_cast_spell_anim);
+        self.death_anim.signals().timeout().connect(on_death_anim);
+        self.ledge_climb_anim
+            .signals()
+            .timeout()
+            .connect(on_ledge_climb_anim);
+        self.slide_anim.signals().timeout().connect(on_slide_anim);
+        self.drop_through
+            .signals()
+            .timeout()
+            .connect(on_drop_through);
+        self.breath.signals().timeout().connect(on_breath);
+        self.down_strike.signals().timeout().connect(on_down_strike);
+        self.hard_landing_anim
+            .signals()
+            .timeout()
+            .connect(on_hard_landing_anim);
+    }
+}
+
+/// The timers the player's state machine starts, stops and checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PlayerTimer {
+    WallJump,
+    DodgeAnim,
+    HealingAnim,
+    HealingCooldown,
+    HurtAnim,
+    ParryAnim,
+    Parry,
+    PerfectParry,
+    Coyote,
+    DodgeCooldown,
+    JumpLimit,
+    SpellCooldown,
+    CastSpellAnim,
+    DownStrike,
+    DeathAnim,
+    LedgeClimbAnim,
+    SlideAnim,
+    HardLandingAnim,
+}
+
+/// Timers as seen by the player's state machine, so it can run against fakes in tests.
+pub trait SMTimers {
+    fn start(&mut self, timer: PlayerTimer);
+    fn stop(&mut self, timer: PlayerTimer);
+    fn is_stopped(&self, timer: PlayerTimer) -> bool;
+}
+
+impl SMTimers for PlayerTimers {
+    fn start(&mut self, timer: PlayerTimer) {
+        self.get(timer).clone().start();
+    }
+
+    fn stop(&mut self, timer: PlayerTimer) {
+        self.get(timer).clone().stop();
+    }
+
+    fn is_stopped(&self, timer: PlayerTimer) -> bool {
+        self.get(timer).is_stopped()
     }
 }
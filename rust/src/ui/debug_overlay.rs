@@ -0,0 +1,92 @@
+use std::fmt::Write;
+
+use godot::{
+    classes::{CanvasLayer, ICanvasLayer, InputEvent, Label, Os, Timer},
+    prelude::*,
+};
+
+use crate::utils::{global_data_singleton::GlobalData, sm_history};
+
+/// How many of the player's transitions are shown.
+const PLAYER_TRANSITIONS: usize = 8;
+
+/// Shows the player's state machine, velocity, running timers and resources, along with the
+/// state of every enemy in the map. Toggled with `dev_debug_overlay` in debug builds.
+#[derive(GodotClass)]
+#[class(init, base=CanvasLayer)]
+pub struct DebugOverlay {
+    #[init(node = "Label")]
+    label: OnReady<Gd<Label>>,
+    base: Base<CanvasLayer>,
+}
+
+#[godot_api]
+impl ICanvasLayer for DebugOverlay {
+    fn ready(&mut self) {
+        self.base_mut().set_visible(false);
+    }
+
+    fn input(&mut self, event: Gd<InputEvent>) {
+        if event.is_action_pressed("dev_debug_overlay") && Os::singleton().is_debug_build() {
+            let visible = !self.base().is_visible();
+            self.base_mut().set_visible(visible);
+        }
+    }
+
+    fn process(&mut self, _delta: f32) {
+        if !self.base().is_visible() {
+            return;
+        }
+        let text = self.describe();
+        self.label.set_text(&text);
+    }
+}
+
+impl DebugOverlay {
+    fn describe(&self) -> String {
+        let Some(player) = GlobalData::singleton().bind().player.clone() else {
+            return "No player".to_string();
+        };
+        let mut text = String::new();
+        {
+            let p = player.bind();
+            let history = p.sm_history();
+            let res = &p.resources;
+            let _ = writeln!(text, "State: {}", history.state);
+            let v = player.get_velocity();
+            let _ = writeln!(text, "Velocity: ({:.1}, {:.1})", v.x, v.y);
+            let _ = writeln!(
+                text,
+                "Health: {}/{}  Stamina: {}/{}  Mana: {}",
+                res.health().amount(),
+                res.health().max(),
+                res.stamina().amount(),
+                res.stamina().max(),
+                res.mana().amount()
+            );
+            text.push_str("Timers:");
+            for timer in player
+                .get_children()
+                .iter_shared()
+                .filter_map(|n| n.try_cast::<Timer>().ok())
+                .filter(|t| !t.is_stopped())
+            {
+                let _ = write!(text, " {} {:.2}", timer.get_name(), timer.get_time_left());
+            }
+            text.push_str("\nTransitions:\n");
+            for transition in history.history.latest(PLAYER_TRANSITIONS) {
+                let _ = writeln!(text, "  {transition}");
+            }
+        }
+
+        text.push_str("Enemies:\n");
+        for enemy in sm_history::enemy_histories(&player.upcast()) {
+            let _ = write!(text, "  {}: {}", enemy.name, enemy.state);
+            if let Some(last) = enemy.history.latest(1).next() {
+                let _ = write!(text, " ({last})");
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
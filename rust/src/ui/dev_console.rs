@@ -0,0 +1,113 @@
+use godot::{
+    classes::{CanvasLayer, ICanvasLayer, InputEvent, LineEdit, Os, RichTextLabel},
+    prelude::*,
+};
+
+use crate::utils::global_data_singleton::GlobalData;
+
+/// In-game console for running commands from the `CommandRegistry` in `GlobalData`. Only
+/// available in debug builds. The game is paused while it's open.
+#[derive(GodotClass)]
+#[class(init, base=CanvasLayer)]
+pub struct DevConsole {
+    #[init(node = "Panel/VBoxContainer/Output")]
+    output: OnReady<Gd<RichTextLabel>>,
+    #[init(node = "Panel/VBoxContainer/Input")]
+    input: OnReady<Gd<LineEdit>>,
+    /// Previously run commands, oldest first.
+    history: Vec<String>,
+    /// Index into `history` while browsing it with up and down.
+    history_pos: Option<usize>,
+    base: Base<CanvasLayer>,
+}
+
+#[godot_api]
+impl ICanvasLayer for DevConsole {
+    fn ready(&mut self) {
+        self.base_mut().set_visible(false);
+        self.input
+            .signals()
+            .text_submitted()
+            .connect_other(&self.to_gd(), Self::on_text_submitted);
+    }
+
+    fn input(&mut self, event: Gd<InputEvent>) {
+        if event.is_action_pressed("dev_console") && Os::singleton().is_debug_build() {
+            let visible = !self.base().is_visible();
+            self.set_open(visible);
+        } else if !self.base().is_visible() {
+            return;
+        } else if event.is_action_pressed("ui_up") {
+            self.browse_history(-1);
+        } else if event.is_action_pressed("ui_down") {
+            self.browse_history(1);
+        } else {
+            return;
+        }
+        self.base().get_viewport().unwrap().set_input_as_handled();
+    }
+}
+
+#[godot_api]
+impl DevConsole {
+    fn set_open(&mut self, open: bool) {
+        self.base_mut().set_visible(open);
+        self.base().get_tree().unwrap().set_pause(open);
+        if open {
+            self.input.clear();
+            self.input.grab_focus();
+        } else {
+            self.input.release_focus();
+        }
+    }
+
+    fn browse_history(&mut self, step: isize) {
+        if self.history.is_empty() {
+            return;
+        }
+        let last = self.history.len() - 1;
+        let pos = match self.history_pos {
+            None if step < 0 => Some(last),
+            None => None,
+            Some(pos) => pos.checked_add_signed(step).filter(|p| *p <= last),
+        };
+        self.history_pos = pos;
+        let text = pos.map_or(String::new(), |p| self.history[p].clone());
+        self.input.set_text(&text);
+        self.input.set_caret_column(text.len() as i32);
+    }
+
+    fn on_text_submitted(&mut self, text: GString) {
+        let line = text.to_string();
+        self.input.clear();
+        self.history_pos = None;
+        if line.trim().is_empty() {
+            return;
+        }
+        if self.history.last() != Some(&line) {
+            self.history.push(line.clone());
+        }
+        self.print(&format!("> {line}"));
+
+        // The guard on `GlobalData` is dropped before running, as commands may bind it themselves.
+        let resolved = GlobalData::singleton()
+            .bind()
+            .console_commands
+            .resolve(&line);
+        match resolved.and_then(|(command, args)| command.run(&args)) {
+            Ok(out) if out.is_empty() => {}
+            Ok(out) => self.print(&out),
+            Err(e) => self.print_error(&e),
+        }
+    }
+
+    fn print(&mut self, text: &str) {
+        self.output.add_text(&format!("{text}\n"));
+    }
+
+    fn print_error(&mut self, text: &str) {
+        self.output.push_color(Color::RED);
+        self.print(text);
+        self.output.pop();
+    }
+}
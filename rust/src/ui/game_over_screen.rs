@@ -0,0 +1,52 @@
+use godot::{
+    classes::{Button, CanvasLayer, ICanvasLayer, Label},
+    prelude::*,
+};
+
+use crate::entities::combat::resources::DamageSource;
+
+/// Shown after the player's death animation finishes. Requests a respawn when the player
+/// confirms.
+#[derive(GodotClass)]
+#[class(init, base=CanvasLayer)]
+pub struct GameOverScreen {
+    #[init(node = "Control/CenterContainer/VBoxContainer/RespawnButton")]
+    respawn_button: OnReady<Gd<Button>>,
+    #[init(node = "Control/CenterContainer/VBoxContainer/Label")]
+    label: OnReady<Gd<Label>>,
+    base: Base<CanvasLayer>,
+}
+
+#[godot_api]
+impl ICanvasLayer for GameOverScreen {
+    fn ready(&mut self) {
+        self.base_mut().set_visible(false);
+        self.respawn_button
+            .signals()
+            .pressed()
+            .connect_other(&self.to_gd(), Self::on_respawn_pressed);
+    }
+}
+
+#[godot_api]
+impl GameOverScreen {
+    #[signal]
+    pub fn respawn_requested();
+
+    /// Shows the screen, describing the damage that killed the player.
+    pub fn show_screen(&mut self, cause: Option<DamageSource>) {
+        let text = match cause {
+            Some(DamageSource::Fall) => "You Fell",
+            Some(DamageSource::Drowning) => "You Drowned",
+            Some(DamageSource::Attack) | None => "You Died",
+        };
+        self.label.set_text(text);
+        self.base_mut().set_visible(true);
+        self.respawn_button.grab_focus();
+    }
+
+    fn on_respawn_pressed(&mut self) {
+        self.base_mut().set_visible(false);
+        self.signals().respawn_requested().emit();
+    }
+}
@@ -8,7 +8,7 @@ use godot::{
 
 use crate::{
     entities::player::{item_component::ItemComponent, main_character::MainCharacter},
-    utils::global_data_singleton::GlobalData,
+    utils::{global_data_singleton::GlobalData, input_hanlder::Action},
 };
 
 #[derive(GodotClass)]
@@ -71,12 +71,12 @@ impl ICanvasLayer for InventoryMenu {
     }
 
     fn unhandled_input(&mut self, event: Gd<InputEvent>) {
-        if event.is_action_pressed("inventory") && !self.base().is_visible() {
+        if event.is_action_pressed(Action::Inventory.name()) && !self.base().is_visible() {
             self.base_mut().set_visible(true);
             self.set_bead_list_icons();
-        } else if event.is_action_pressed("inventory") && self.base().is_visible() {
+        } else if event.is_action_pressed(Action::Inventory.name()) && self.base().is_visible() {
             self.base_mut().set_visible(false);
-        } else if event.is_action_pressed("equip") && self.base().is_visible() {
+        } else if event.is_action_pressed(Action::Equip.name()) && self.base().is_visible() {
             //             // TODO: Used for testing. Remove later.
             println!("Testing equipping relic. Remove me.");
             self.on_relic_activated(0);
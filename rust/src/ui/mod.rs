@@ -1,3 +1,6 @@
+mod debug_overlay;
+mod dev_console;
+pub mod game_over_screen;
 mod health_bar;
 mod inventory_menu;
 mod player_stats_ui_singleton;
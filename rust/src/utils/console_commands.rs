@@ -0,0 +1,282 @@
+use std::collections::BTreeMap;
+
+use godot::{
+    classes::{Node, Node2D, PackedScene, ResourceLoader},
+    prelude::*,
+};
+
+use crate::{
+    entities::{
+        entity_stats::Stat,
+        player::{item_component::ItemComponent, main_character::MainCharacter},
+    },
+    utils::{global_data_singleton::GlobalData, time_control},
+    world::{item::Item, main_node::Main},
+};
+
+/// Runs a console command with its arguments, returning the text to print.
+pub type CommandFn = fn(&[&str]) -> Result<String, String>;
+
+#[derive(Clone, Copy)]
+pub struct Command {
+    pub usage: &'static str,
+    pub help: &'static str,
+    min_args: usize,
+    max_args: usize,
+    run: CommandFn,
+}
+
+impl Command {
+    pub fn run(&self, args: &[&str]) -> Result<String, String> {
+        if args.len() < self.min_args || args.len() > self.max_args {
+            return Err(format!("Usage: {}", self.usage));
+        }
+        (self.run)(args)
+    }
+}
+
+/// Commands available in the dev console. Held by `GlobalData` so any module can add its own
+/// with `register`.
+#[derive(Default, Clone)]
+pub struct CommandRegistry {
+    commands: BTreeMap<&'static str, Command>,
+}
+
+impl CommandRegistry {
+    /// A registry with the gameplay commands every build has.
+    pub fn with_builtin() -> Self {
+        let mut registry = Self::default();
+        registry.register("help", "help", "Lists commands", 0, 0, help);
+        registry.register(
+            "give",
+            "give <item_id>",
+            "Gives the player an item",
+            1,
+            1,
+            give,
+        );
+        registry.register(
+            "spawn",
+            "spawn <enemy> [x y]",
+            "Spawns an enemy from res://entities/enemies, next to the player by default",
+            1,
+            3,
+            spawn,
+        );
+        registry.register("god", "god", "Toggles invulnerability", 0, 0, god);
+        registry.register(
+            "noclip",
+            "noclip",
+            "Toggles flying through walls",
+            0,
+            0,
+            noclip,
+        );
+        registry.register(
+            "set_stat",
+            "set_stat <stat> <value>",
+            "Sets one of the player's stats",
+            2,
+            2,
+            set_stat,
+        );
+        registry.register(
+            "load_map",
+            "load_map <scene>",
+            "Loads a map from res://maps, or a full res:// path",
+            1,
+            1,
+            load_map,
+        );
+        registry.register(
+            "heal",
+            "heal",
+            "Refills health, stamina and mana",
+            0,
+            0,
+            heal,
+        );
+        registry.register(
+            "timescale",
+            "timescale <scale>",
+            "Sets the engine's time scale",
+            1,
+            1,
+            timescale,
+        );
+        registry
+    }
+
+    pub fn register(
+        &mut self,
+        name: &'static str,
+        usage: &'static str,
+        help: &'static str,
+        min_args: usize,
+        max_args: usize,
+        run: CommandFn,
+    ) {
+        self.commands.insert(
+            name,
+            Command {
+                usage,
+                help,
+                min_args,
+                max_args,
+                run,
+            },
+        );
+    }
+
+    /// Splits `line` into a command and its arguments. The command is copied out so it can run
+    /// without `GlobalData` being bound.
+    pub fn resolve<'a>(&self, line: &'a str) -> Result<(Command, Vec<&'a str>), String> {
+        let mut words = line.split_whitespace();
+        let name = words.next().ok_or_else(String::new)?;
+        let command = self
+            .commands
+            .get(name)
+            .copied()
+            .ok_or_else(|| format!("Unknown command '{name}'. Try 'help'."))?;
+        Ok((command, words.collect()))
+    }
+
+    pub fn commands(&self) -> impl Iterator<Item = (&'static str, &Command)> {
+        self.commands.iter().map(|(name, c)| (*name, c))
+    }
+}
+
+fn player() -> Result<Gd<MainCharacter>, String> {
+    GlobalData::singleton()
+        .bind()
+        .player
+        .clone()
+        .ok_or_else(|| "No player".to_string())
+}
+
+fn main_node() -> Result<Gd<Main>, String> {
+    player()?
+        .get_tree()
+        .and_then(|tree| tree.get_current_scene())
+        .and_then(|scene| scene.try_cast::<Main>().ok())
+        .ok_or_else(|| "No main scene".to_string())
+}
+
+fn parse<T: std::str::FromStr>(arg: &str) -> Result<T, String> {
+    arg.parse().map_err(|_| format!("Invalid value '{arg}'"))
+}
+
+fn help(_: &[&str]) -> Result<String, String> {
+    let registry = GlobalData::singleton().bind().console_commands.clone();
+    Ok(registry
+        .commands()
+        .map(|(_, c)| format!("{} - {}", c.usage, c.help))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn give(args: &[&str]) -> Result<String, String> {
+    let item = Item::from_id(args[0]).ok_or_else(|| format!("Unknown item '{}'", args[0]))?;
+    let name = item.name.clone();
+    let mut items = player()?.get_node_as::<ItemComponent>("ItemComponent");
+    items.bind_mut().add_item(item);
+    Ok(format!("Gave {name}"))
+}
+
+fn spawn(args: &[&str]) -> Result<String, String> {
+    let path = format!("res://entities/enemies/{}.tscn", args[0]);
+    if !ResourceLoader::singleton().exists(&path) {
+        return Err(format!("No enemy scene at {path}"));
+    }
+    let position = match args {
+        [_, x, y] => Vector2::new(parse(x)?, parse(y)?),
+        [_] => player()?.get_global_position() + Vector2::new(64.0, -16.0),
+        _ => return Err("Both x and y are needed".to_string()),
+    };
+    let mut enemy = load::<PackedScene>(&path)
+        .try_instantiate_as::<Node2D>()
+        .ok_or_else(|| format!("{path} isn't a Node2D"))?;
+    let map = main_node()?.bind().current_map();
+    map.get_node_as::<Node>("Enemies").add_child(&enemy);
+    enemy.set_global_position(position);
+    Ok(format!("Spawned {} at {position}", args[0]))
+}
+
+fn god(_: &[&str]) -> Result<String, String> {
+    let mut player = player()?;
+    let mut player = player.bind_mut();
+    player.god_mode = !player.god_mode;
+    Ok(format!("God mode {}", on_off(player.god_mode)))
+}
+
+fn noclip(_: &[&str]) -> Result<String, String> {
+    let mut player = player()?;
+    let mut player = player.bind_mut();
+    let enabled = !player.is_noclip();
+    player.set_noclip(enabled);
+    Ok(format!("Noclip {}", on_off(enabled)))
+}
+
+fn set_stat(args: &[&str]) -> Result<String, String> {
+    let stat = Stat::from_name(args[0]).ok_or_else(|| format!("Unknown stat '{}'", args[0]))?;
+    let value = parse::<i64>(args[1])?;
+    player()?.bind_mut().stats.get_mut(stat).0 = value;
+    Ok(format!("Set {} to {value}", args[0]))
+}
+
+fn load_map(args: &[&str]) -> Result<String, String> {
+    let path = if args[0].starts_with("res://") {
+        args[0].to_string()
+    } else {
+        format!("res://maps/{}.tscn", args[0])
+    };
+    if !ResourceLoader::singleton().exists(&path) {
+        return Err(format!("No map at {path}"));
+    }
+    main_node()?
+        .bind_mut()
+        .change_map(load::<PackedScene>(&path), None);
+    Ok(format!("Loading {path}"))
+}
+
+fn heal(_: &[&str]) -> Result<String, String> {
+    player()?.bind_mut().rest();
+    Ok("Healed".to_string())
+}
+
+fn timescale(args: &[&str]) -> Result<String, String> {
+    let scale = parse::<f64>(args[0])?;
+    if scale <= 0.0 {
+        return Err("Time scale must be positive".to_string());
+    }
+    time_control::set_base_scale(scale);
+    Ok(format!("Time scale set to {scale}"))
+}
+
+fn on_off(enabled: bool) -> &'static str {
+    if enabled { "on" } else { "off" }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::utils::console_commands::CommandRegistry;
+
+    fn echo(args: &[&str]) -> Result<String, String> {
+        Ok(args.join(" "))
+    }
+
+    #[test]
+    fn test_command_resolution_and_arity() {
+        let mut registry = CommandRegistry::default();
+        registry.register("echo", "echo <a> [b]", "Echoes", 1, 2, echo);
+
+        let (command, args) = registry.resolve("  echo hello   world ").unwrap();
+        assert_eq!(command.run(&args), Ok("hello world".to_string()));
+
+        let (command, args) = registry.resolve("echo").unwrap();
+        assert_eq!(command.run(&args), Err("Usage: echo <a> [b]".to_string()));
+
+        assert!(registry.resolve("nope").is_err());
+        assert!(registry.resolve("   ").is_err());
+    }
+}
@@ -2,9 +2,17 @@ use godot::prelude::*;
 
 use crate::{
     entities::{
-        entity_stats::StatModifier, movements::Direction, player::main_character::MainCharacter,
+        entity_stats::StatModifier, movement_tuning::MovementTuning, movements::Direction,
+        player::main_character::MainCharacter,
+    },
+    utils::{
+        console_commands::CommandRegistry, input_device::InputDevice, time_control::TimeControl,
+    },
+    world::{
+        checkpoint::{Checkpoint, CheckpointData},
+        item::GameItem,
+        world_state::WorldState,
     },
-    world::item::GameItem,
 };
 
 #[derive(GodotClass)]
@@ -14,6 +22,17 @@ pub struct GlobalData {
     pub player_pos: Vector2,
     pub player: Option<Gd<MainCharacter>>,
     pub player_dir: Direction,
+    /// The last checkpoint the player rested at.
+    pub checkpoint: Option<CheckpointData>,
+    /// Persistent state of map objects, keyed per map.
+    pub world_state: WorldState,
+    /// The device the player last used.
+    pub input_device: InputDevice,
+    /// Commands the dev console can run.
+    #[init(val = CommandRegistry::with_builtin())]
+    pub console_commands: CommandRegistry,
+    /// Hitstop and slow motion.
+    pub time_control: TimeControl,
     #[init(val = SignalHandler::new_alloc())]
     pub sigs: Gd<SignalHandler>,
     base: Base<Object>,
@@ -30,10 +49,6 @@ impl GlobalData {
             .unwrap()
             .cast::<Self>()
     }
-
-    pub fn get_player_mut(&mut self) -> Option<&mut Gd<MainCharacter>> {
-        self.player.as_mut()
-    }
 }
 
 #[derive(Default)]
@@ -61,4 +76,17 @@ impl SignalHandler {
 
     #[signal]
     pub fn modifier_removed(modifier: Gd<StatModifier>);
+
+    #[signal]
+    pub fn player_rested(checkpoint: Gd<Checkpoint>);
+
+    #[signal]
+    pub fn movement_tuning_reloaded(tuning: Gd<MovementTuning>);
+
+    #[signal]
+    pub fn input_device_changed(device: InputDevice);
+
+    /// Emitted when the player selects a different hot spell slot.
+    #[signal]
+    pub fn selected_spell_changed(slot: i64);
 }
@@ -0,0 +1,404 @@
+use std::collections::HashMap;
+
+use godot::{
+    builtin::{Array, GString, PackedStringArray},
+    classes::{
+        ConfigFile, InputEvent, InputEventJoypadButton, InputEventJoypadMotion, InputEventKey,
+        InputEventMouseButton, InputMap, Object,
+    },
+    global::{Error, JoyAxis, JoyButton, Key, MouseButton, godot_warn},
+    meta::ToGodot,
+    obj::{Base, EngineEnum, Gd, NewGd, Singleton},
+    prelude::{GodotClass, godot_api},
+};
+
+use crate::utils::input_hanlder::Action;
+
+const BINDINGS_PATH: &str = "user://controls.cfg";
+
+/// Device id of events that match any device.
+const ALL_DEVICES: i32 = -1;
+
+/// A single key, button or stick direction bound to an action.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Binding {
+    /// A physical key, so bindings follow key position rather than keyboard layout.
+    Key(Key),
+    JoyButton(JoyButton),
+    /// A stick or trigger pushed in the positive or negative direction.
+    JoyAxis(JoyAxis, bool),
+    MouseButton(MouseButton),
+}
+
+impl Binding {
+    pub fn from_event(event: &Gd<InputEvent>) -> Option<Self> {
+        if let Ok(key) = event.clone().try_cast::<InputEventKey>() {
+            let code = match key.get_physical_keycode() {
+                Key::NONE => key.get_keycode(),
+                code => code,
+            };
+            (code != Key::NONE).then_some(Binding::Key(code))
+        } else if let Ok(button) = event.clone().try_cast::<InputEventJoypadButton>() {
+            Some(Binding::JoyButton(button.get_button_index()))
+        } else if let Ok(motion) = event.clone().try_cast::<InputEventJoypadMotion>() {
+            Some(Binding::JoyAxis(
+                motion.get_axis(),
+                motion.get_axis_value() >= 0.0,
+            ))
+        } else if let Ok(button) = event.clone().try_cast::<InputEventMouseButton>() {
+            Some(Binding::MouseButton(button.get_button_index()))
+        } else {
+            None
+        }
+    }
+
+    /// A new event for the binding, matching every device like the events in `project.godot`.
+    pub fn to_event(self) -> Gd<InputEvent> {
+        let mut event: Gd<InputEvent> = match self {
+            Binding::Key(code) => {
+                let mut event = InputEventKey::new_gd();
+                event.set_physical_keycode(code);
+                event.upcast()
+            }
+            Binding::JoyButton(button) => {
+                let mut event = InputEventJoypadButton::new_gd();
+                event.set_button_index(button);
+                event.upcast()
+            }
+            Binding::JoyAxis(axis, positive) => {
+                let mut event = InputEventJoypadMotion::new_gd();
+                event.set_axis(axis);
+                event.set_axis_value(if positive { 1.0 } else { -1.0 });
+                event.upcast()
+            }
+            Binding::MouseButton(button) => {
+                let mut event = InputEventMouseButton::new_gd();
+                event.set_button_index(button);
+                event.upcast()
+            }
+        };
+        event.set_device(ALL_DEVICES);
+        event
+    }
+
+    /// Serializes the binding for the controls file, e.g. `key:87` or `joy_axis:1:-`.
+    pub fn to_config(self) -> String {
+        match self {
+            Binding::Key(code) => format!("key:{}", code.ord()),
+            Binding::JoyButton(button) => format!("joy_button:{}", button.ord()),
+            Binding::JoyAxis(axis, positive) => {
+                format!(
+                    "joy_axis:{}:{}",
+                    axis.ord(),
+                    if positive { "+" } else { "-" }
+                )
+            }
+            Binding::MouseButton(button) => format!("mouse:{}", button.ord()),
+        }
+    }
+
+    pub fn from_config(value: &str) -> Option<Self> {
+        let mut parts = value.split(':');
+        let kind = parts.next()?;
+        let ord = parts.next()?.parse::<i32>().ok()?;
+        match kind {
+            "key" => Key::try_from_ord(ord).map(Binding::Key),
+            "joy_button" => JoyButton::try_from_ord(ord).map(Binding::JoyButton),
+            "joy_axis" => {
+                let positive = match parts.next()? {
+                    "+" => true,
+                    "-" => false,
+                    _ => return None,
+                };
+                JoyAxis::try_from_ord(ord).map(|axis| Binding::JoyAxis(axis, positive))
+            }
+            "mouse" => MouseButton::try_from_ord(ord).map(Binding::MouseButton),
+            _ => None,
+        }
+    }
+}
+
+/// Returned when a binding is already used by another action.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BindingConflict {
+    pub action: Action,
+    pub binding: Binding,
+}
+
+/// The bindings of every `Action`. Changes are applied to the `InputMap` with `apply` and
+/// persisted to `user://` with `save`.
+#[derive(Default, Clone, Debug)]
+pub struct InputBindings {
+    bindings: HashMap<Action, Vec<Binding>>,
+}
+
+impl InputBindings {
+    /// Reads the bindings currently in the `InputMap`.
+    pub fn from_input_map() -> Self {
+        let mut map = InputMap::singleton();
+        let mut bindings = HashMap::new();
+        for action in Action::ALL {
+            if !map.has_action(action.name()) {
+                continue;
+            }
+            let events = map.action_get_events(action.name());
+            let action_bindings = events
+                .iter_shared()
+                .filter_map(|e| Binding::from_event(&e))
+                .collect();
+            bindings.insert(action, action_bindings);
+        }
+        Self { bindings }
+    }
+
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// The other action `binding` is already bound to, if any.
+    pub fn conflict(&self, action: Action, binding: Binding) -> Option<BindingConflict> {
+        self.bindings
+            .iter()
+            .find(|(other, bindings)| **other != action && bindings.contains(&binding))
+            .map(|(other, _)| BindingConflict {
+                action: *other,
+                binding,
+            })
+    }
+
+    /// Replaces the binding in `slot`, or adds one if the action has fewer bindings. Fails if
+    /// another action already uses `binding`.
+    pub fn set_binding(
+        &mut self,
+        action: Action,
+        slot: usize,
+        binding: Binding,
+    ) -> Result<(), BindingConflict> {
+        if let Some(conflict) = self.conflict(action, binding) {
+            return Err(conflict);
+        }
+        let bindings = self.bindings.entry(action).or_default();
+        match bindings.get_mut(slot) {
+            Some(b) => *b = binding,
+            None => bindings.push(binding),
+        }
+        Ok(())
+    }
+
+    pub fn unbind(&mut self, action: Action, binding: Binding) {
+        if let Some(bindings) = self.bindings.get_mut(&action) {
+            bindings.retain(|b| *b != binding);
+        }
+    }
+
+    /// Updates every action in the `InputMap` to these bindings.
+    pub fn apply(&self) {
+        for action in self.bindings.keys() {
+            self.apply_action(*action);
+        }
+    }
+
+    /// Updates the action's events in the `InputMap` to its bindings. Events that stay bound are
+    /// kept as they are, so what a `Binding` can't express, like key modifiers, survives. Events
+    /// that aren't a `Binding` at all are left alone.
+    fn apply_action(&self, action: Action) {
+        let mut map = InputMap::singleton();
+        if !map.has_action(action.name()) {
+            map.add_action(action.name());
+        }
+        let mut current = map
+            .action_get_events(action.name())
+            .iter_shared()
+            .collect::<Vec<_>>();
+        map.action_erase_events(action.name());
+        for binding in self.bindings(action) {
+            let event = match current
+                .iter()
+                .position(|e| Binding::from_event(e) == Some(*binding))
+            {
+                Some(i) => current.remove(i),
+                None => binding.to_event(),
+            };
+            map.action_add_event(action.name(), &event);
+        }
+        for event in current {
+            if Binding::from_event(&event).is_none() {
+                map.action_add_event(action.name(), &event);
+            }
+        }
+    }
+
+    /// Writes the bindings to `user://`.
+    pub fn save(&self) -> Result<(), Error> {
+        let mut file = ConfigFile::new_gd();
+        for (action, bindings) in &self.bindings {
+            let values = bindings
+                .iter()
+                .map(|b| b.to_config().as_str().into())
+                .collect::<PackedStringArray>();
+            file.set_value("bindings", action.name(), &values.to_variant());
+        }
+        match file.save(BINDINGS_PATH) {
+            Error::OK => Ok(()),
+            e => Err(e),
+        }
+    }
+
+    /// Reads the user's bindings from `user://`, if they have changed any.
+    pub fn load() -> Option<Self> {
+        let mut file = ConfigFile::new_gd();
+        if file.load(BINDINGS_PATH) != Error::OK {
+            return None;
+        }
+        let mut bindings = HashMap::new();
+        for key in file.get_section_keys("bindings").as_slice() {
+            let Some(action) = Action::from_name(&key.to_string()) else {
+                continue;
+            };
+            let values = file
+                .get_value("bindings", key)
+                .try_to::<PackedStringArray>()
+                .unwrap_or_default();
+            let action_bindings = values
+                .as_slice()
+                .iter()
+                .filter_map(|v| Binding::from_config(&v.to_string()))
+                .collect();
+            bindings.insert(action, action_bindings);
+        }
+        Some(Self { bindings })
+    }
+
+    /// Applies the user's saved bindings on top of the project's defaults.
+    pub fn load_and_apply() {
+        if let Some(bindings) = Self::load() {
+            bindings.apply();
+        }
+    }
+
+    /// Rebinds `action` at runtime and saves the result. With `replace`, a conflicting binding is
+    /// removed from the other action instead of failing.
+    pub fn rebind(
+        action: Action,
+        slot: usize,
+        binding: Binding,
+        replace: bool,
+    ) -> Result<(), BindingConflict> {
+        let mut bindings = Self::from_input_map();
+        if replace && let Some(conflict) = bindings.conflict(action, binding) {
+            bindings.unbind(conflict.action, binding);
+            bindings.apply_action(conflict.action);
+        }
+        bindings.set_binding(action, slot, binding)?;
+        bindings.apply_action(action);
+        if let Err(e) = bindings.save() {
+            godot_warn!("Failed to save controls: {e:?}");
+        }
+        Ok(())
+    }
+
+    /// Restores the bindings from `project.godot` and saves them.
+    pub fn reset_to_defaults() {
+        InputMap::singleton().load_from_project_settings();
+        if let Err(e) = Self::from_input_map().save() {
+            godot_warn!("Failed to save controls: {e:?}");
+        }
+    }
+}
+
+/// Exposes rebinding to GDScript menus.
+#[derive(GodotClass)]
+#[class(init, base = Object)]
+pub struct Controls {
+    base: Base<Object>,
+}
+
+#[godot_api]
+impl Controls {
+    /// Binds `event` to `action`, replacing the binding in `slot`. If another action already uses
+    /// the binding it is taken from that action when `replace` is set, otherwise nothing changes.
+    /// Returns the name of the conflicting action, or an empty string.
+    #[func]
+    fn rebind(action: GString, slot: u32, event: Gd<InputEvent>, replace: bool) -> GString {
+        let (Some(action), Some(binding)) = (
+            Action::from_name(&action.to_string()),
+            Binding::from_event(&event),
+        ) else {
+            godot_warn!("Can't bind {event} to {action}");
+            return GString::new();
+        };
+        match InputBindings::rebind(action, slot as usize, binding, replace) {
+            Ok(()) => GString::new(),
+            Err(conflict) => conflict.action.name().into(),
+        }
+    }
+
+    /// The events currently bound to `action`, for displaying in a menu.
+    #[func]
+    fn get_bindings(action: GString) -> Array<Gd<InputEvent>> {
+        let Some(action) = Action::from_name(&action.to_string()) else {
+            return Array::new();
+        };
+        InputBindings::from_input_map()
+            .bindings(action)
+            .iter()
+            .map(|b| b.to_event())
+            .collect()
+    }
+
+    #[func]
+    fn reset_to_defaults() {
+        InputBindings::reset_to_defaults();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use godot::global::{JoyAxis, Key};
+
+    use crate::utils::{
+        input_bindings::{Binding, BindingConflict, InputBindings},
+        input_hanlder::Action,
+    };
+
+    #[test]
+    fn test_binding_config_round_trip() {
+        for binding in [
+            Binding::Key(Key::W),
+            Binding::JoyAxis(JoyAxis::LEFT_X, false),
+        ] {
+            assert_eq!(Binding::from_config(&binding.to_config()), Some(binding));
+        }
+        assert_eq!(Binding::from_config("joy_axis:0"), None);
+        assert_eq!(Binding::from_config("wheel:1"), None);
+    }
+
+    #[test]
+    fn test_rebinding_conflicts() {
+        let mut bindings = InputBindings::default();
+        bindings
+            .set_binding(Action::Jump, 0, Binding::Key(Key::SPACE))
+            .unwrap();
+        assert_eq!(
+            bindings.set_binding(Action::Dodge, 0, Binding::Key(Key::SPACE)),
+            Err(BindingConflict {
+                action: Action::Jump,
+                binding: Binding::Key(Key::SPACE),
+            })
+        );
+
+        // Rebinding an action to a binding it already has isn't a conflict.
+        bindings
+            .set_binding(Action::Jump, 0, Binding::Key(Key::SPACE))
+            .unwrap();
+
+        bindings.unbind(Action::Jump, Binding::Key(Key::SPACE));
+        bindings
+            .set_binding(Action::Dodge, 3, Binding::Key(Key::SPACE))
+            .unwrap();
+        assert_eq!(
+            bindings.bindings(Action::Dodge),
+            &[Binding::Key(Key::SPACE)]
+        );
+    }
+}
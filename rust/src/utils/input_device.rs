@@ -0,0 +1,59 @@
+use godot::{
+    classes::{
+        Input, InputEvent, InputEventJoypadButton, InputEventJoypadMotion, InputEventKey,
+        InputEventMouseButton,
+    },
+    obj::{Gd, Singleton},
+    prelude::{GString, GodotConvert},
+};
+
+use crate::utils::global_data_singleton::GlobalData;
+
+/// Joystick movement below this is treated as drift rather than the player picking up the pad.
+const DEVICE_SWITCH_THRESHOLD: f32 = 0.5;
+
+/// The kind of device the player is using, so on-screen prompts can show matching buttons.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, GodotConvert)]
+#[godot(via = GString)]
+pub enum InputDevice {
+    #[default]
+    KeyboardMouse,
+    Gamepad,
+}
+
+impl InputDevice {
+    /// The device `event` came from, if it shows the player switched devices. Mouse movement
+    /// and small stick movements are ignored.
+    pub fn from_event(event: &Gd<InputEvent>) -> Option<Self> {
+        if event.clone().try_cast::<InputEventKey>().is_ok()
+            || event.clone().try_cast::<InputEventMouseButton>().is_ok()
+        {
+            Some(InputDevice::KeyboardMouse)
+        } else if event.clone().try_cast::<InputEventJoypadButton>().is_ok() {
+            Some(InputDevice::Gamepad)
+        } else if let Ok(motion) = event.clone().try_cast::<InputEventJoypadMotion>() {
+            (motion.get_axis_value().abs() >= DEVICE_SWITCH_THRESHOLD)
+                .then_some(InputDevice::Gamepad)
+        } else {
+            None
+        }
+    }
+
+    /// Records the device in use and emits `input_device_changed` if it changed.
+    pub fn set_active(device: InputDevice) {
+        let mut data = GlobalData::singleton();
+        let mut data = data.bind_mut();
+        if data.input_device != device {
+            data.input_device = device;
+            data.sig_handler().input_device_changed().emit(device);
+        }
+    }
+
+    /// Handles a gamepad being plugged in or unplugged. Falls back to keyboard prompts once the
+    /// last gamepad is removed.
+    pub fn on_joy_connection_changed(_device: i64, connected: bool) {
+        if !connected && Input::singleton().get_connected_joypads().is_empty() {
+            Self::set_active(InputDevice::KeyboardMouse);
+        }
+    }
+}
@@ -1,20 +1,325 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
 use godot::{
-    classes::Input,
-    obj::{Gd, WithBaseField},
+    classes::{Input, Os},
+    global::godot_warn,
+    obj::{Gd, Singleton, WithBaseField},
+};
+
+use crate::entities::{
+    entity_stats::Stat, movement_tuning::MovementTuning, player::main_character::MainCharacter,
 };
 
-use crate::entities::{entity_stats::Stat, player::main_character::MainCharacter};
+/// How long attack must be held before releasing it performs a charged attack.
+const CHARGED_ATTACK_TIME: f32 = 2.0;
+
+/// Gameplay and menu actions defined in the project's input map.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    North,
+    South,
+    East,
+    West,
+    Jump,
+    Attack,
+    Dodge,
+    Heal,
+    Parry,
+    Ability1,
+    Ability2,
+    Ability3,
+    /// Casts the spell chosen with `RotateAbilitiesLeft` and `RotateAbilitiesRight`.
+    CastSpell,
+    Interact,
+    Equip,
+    Inventory,
+    RotateAbilitiesLeft,
+    RotateAbilitiesRight,
+}
+
+impl Action {
+    pub const ALL: [Action; 18] = [
+        Action::North,
+        Action::South,
+        Action::East,
+        Action::West,
+        Action::Jump,
+        Action::Attack,
+        Action::Dodge,
+        Action::Heal,
+        Action::Parry,
+        Action::Ability1,
+        Action::Ability2,
+        Action::Ability3,
+        Action::CastSpell,
+        Action::Interact,
+        Action::Equip,
+        Action::Inventory,
+        Action::RotateAbilitiesLeft,
+        Action::RotateAbilitiesRight,
+    ];
+
+    /// Actions tracked by `InputState`, and so recorded and replayed.
+    pub const GAMEPLAY: [Action; 15] = [
+        Action::North,
+        Action::South,
+        Action::East,
+        Action::West,
+        Action::Jump,
+        Action::Attack,
+        Action::Dodge,
+        Action::Heal,
+        Action::Parry,
+        Action::Ability1,
+        Action::Ability2,
+        Action::Ability3,
+        Action::CastSpell,
+        Action::RotateAbilitiesLeft,
+        Action::RotateAbilitiesRight,
+    ];
+
+    /// The action's name in the `InputMap`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::North => "north",
+            Action::South => "south",
+            Action::East => "east",
+            Action::West => "west",
+            Action::Jump => "jump",
+            Action::Attack => "attack",
+            Action::Dodge => "dodge",
+            Action::Heal => "heal",
+            Action::Parry => "parry",
+            Action::Ability1 => "ability_1",
+            Action::Ability2 => "ability_2",
+            Action::Ability3 => "ability_3",
+            Action::CastSpell => "cast_spell",
+            Action::Interact => "interact",
+            Action::Equip => "equip",
+            Action::Inventory => "inventory",
+            Action::RotateAbilitiesLeft => "rotate_abilities_left",
+            Action::RotateAbilitiesRight => "rotate_abilities_right",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.name() == name)
+    }
+
+    /// The action button pressed by this action, if any.
+    fn modifier(self) -> Option<ModifierButton> {
+        match self {
+            Action::Jump => Some(ModifierButton::Jump),
+            Action::Attack => Some(ModifierButton::Attack),
+            Action::Dodge => Some(ModifierButton::Dodge),
+            Action::Heal => Some(ModifierButton::Heal),
+            Action::Parry => Some(ModifierButton::Parry),
+            Action::Ability1 => Some(ModifierButton::Ability1),
+            Action::Ability2 => Some(ModifierButton::Ability2),
+            Action::Ability3 => Some(ModifierButton::Ability3),
+            Action::CastSpell => Some(ModifierButton::CastSpell),
+            _ => None,
+        }
+    }
+}
+
+/// Provides whether an action is currently held. Implemented by Godot's `Input` singleton and by
+/// `InputFrame` so tests and replays can feed synthetic input.
+pub trait InputSource {
+    fn is_pressed(&self, action: Action) -> bool;
+
+    /// How far an analog action is pushed, from 0 to 1, ignoring deadzones.
+    fn strength(&self, action: Action) -> f32 {
+        if self.is_pressed(action) { 1.0 } else { 0.0 }
+    }
+}
+
+impl InputSource for Gd<Input> {
+    fn is_pressed(&self, action: Action) -> bool {
+        self.is_action_pressed(action.name())
+    }
+
+    fn strength(&self, action: Action) -> f32 {
+        self.get_action_raw_strength(action.name())
+    }
+}
+
+/// Rescales analog input so values below `inner` are ignored and values above `outer` are full
+/// strength.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Deadzone {
+    pub inner: f32,
+    pub outer: f32,
+}
+
+impl Default for Deadzone {
+    fn default() -> Self {
+        Self {
+            inner: 0.2,
+            outer: 0.9,
+        }
+    }
+}
+
+impl Deadzone {
+    pub fn apply(&self, value: f32) -> f32 {
+        let scaled = (value.abs() - self.inner) / (self.outer - self.inner).max(f32::EPSILON);
+        scaled.clamp(0.0, 1.0).copysign(value)
+    }
+}
+
+/// The gameplay actions held during a single physics frame.
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub struct InputFrame {
+    /// One bit per action in `Action::GAMEPLAY`.
+    pressed: u16,
+    /// Raw horizontal stick position, from -1 to 1.
+    move_x: f32,
+}
+
+impl InputFrame {
+    #[cfg(test)]
+    pub fn new(pressed: &[Action]) -> Self {
+        let mut frame = Self::default();
+        for action in pressed {
+            if let Some(idx) = Action::GAMEPLAY.iter().position(|a| a == action) {
+                frame.pressed |= 1 << idx;
+            }
+            match action {
+                Action::East => frame.move_x += 1.0,
+                Action::West => frame.move_x -= 1.0,
+                _ => (),
+            }
+        }
+        frame
+    }
+
+    /// Reads the gameplay actions currently held in `source`.
+    pub fn capture(source: &impl InputSource) -> Self {
+        let pressed = Action::GAMEPLAY
+            .iter()
+            .enumerate()
+            .filter(|(_, action)| source.is_pressed(**action))
+            .fold(0, |bits, (idx, _)| bits | 1 << idx);
+        Self {
+            pressed,
+            move_x: source.strength(Action::East) - source.strength(Action::West),
+        }
+    }
+
+    pub fn bits(self) -> u16 {
+        self.pressed
+    }
+
+    pub fn move_x(self) -> f32 {
+        self.move_x
+    }
+
+    pub fn from_parts(pressed: u16, move_x: f32) -> Self {
+        Self { pressed, move_x }
+    }
+}
+
+impl InputSource for InputFrame {
+    fn is_pressed(&self, action: Action) -> bool {
+        Action::GAMEPLAY
+            .iter()
+            .position(|a| *a == action)
+            .is_some_and(|idx| self.pressed & 1 << idx != 0)
+    }
+
+    fn strength(&self, action: Action) -> f32 {
+        match action {
+            Action::East => self.move_x.max(0.0),
+            Action::West => (-self.move_x).max(0.0),
+            _ if self.is_pressed(action) => 1.0,
+            _ => 0.0,
+        }
+    }
+}
+
+/// Per-player input state. Tracks how long actions have been held and which actions were pressed
+/// or released this frame. Updated once per physics frame with `update`.
+#[derive(Default, Clone, Debug)]
+pub struct InputState {
+    /// Held actions and how long they have been held for.
+    held: HashMap<Action, f32>,
+    just_pressed: HashSet<Action>,
+    /// Actions released this frame and how long they were held for.
+    just_released: HashMap<Action, f32>,
+    pub buffer: InputBuffer,
+    pub deadzone: Deadzone,
+    /// Horizontal movement after the deadzone is applied, from -1 to 1.
+    move_x: f32,
+}
+
+impl InputState {
+    pub fn update(&mut self, source: &impl InputSource, delta: f32) {
+        self.buffer.advance(delta as f64);
+        self.just_pressed.clear();
+        self.just_released.clear();
+        self.move_x = self
+            .deadzone
+            .apply(source.strength(Action::East) - source.strength(Action::West));
+        for action in Action::GAMEPLAY {
+            match (source.is_pressed(action), self.held.get(&action).copied()) {
+                (true, Some(time)) => {
+                    self.held.insert(action, time + delta);
+                }
+                (true, None) => {
+                    self.held.insert(action, delta);
+                    self.just_pressed.insert(action);
+                    if let Some(button) = action.modifier() {
+                        self.buffer.push(button);
+                    }
+                }
+                (false, Some(time)) => {
+                    self.held.remove(&action);
+                    self.just_released.insert(action, time);
+                }
+                (false, None) => (),
+            }
+        }
+    }
+
+    pub fn is_pressed(&self, action: Action) -> bool {
+        self.held.contains_key(&action)
+    }
+
+    pub fn is_just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    pub fn is_just_released(&self, action: Action) -> bool {
+        self.just_released.contains_key(&action)
+    }
 
-// The time that has passed since the player began holding the attack button.
-// TODO: Maybe use a Mutex.
-static mut CHARGE_ATTACK_TIME: f32 = 0.0;
-static mut JUMPING_TIME: f32 = 0.0;
+    /// How long the action has been held, or was held for if it was released this frame.
+    pub fn held_time(&self, action: Action) -> f32 {
+        self.held
+            .get(&action)
+            .or_else(|| self.just_released.get(&action))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn move_x(&self) -> f32 {
+        self.move_x
+    }
 
-/// Horizontal movement buttons.
+    /// Progress towards a charged attack, from 0 to 1.
+    pub fn charge_progress(&self) -> f32 {
+        (self.held_time(Action::Attack) / CHARGED_ATTACK_TIME).min(1.0)
+    }
+}
+
+/// Movement buttons. Down takes priority over horizontal movement, which takes priority over up.
 #[derive(Clone, PartialEq, Eq, Debug, Copy)]
 pub enum MoveButton {
     Left,
     Right,
+    Down,
+    Up,
 }
 
 /// Action buttons.
@@ -30,107 +335,206 @@ pub enum ModifierButton {
     Ability1,
     Ability2,
     Ability3,
+    CastSpell,
 }
 
-/// Player inputs, used by the state machine.
+impl ModifierButton {
+    fn bit(self) -> u16 {
+        let idx = match self {
+            ModifierButton::Dodge => 0,
+            ModifierButton::Jump => 1,
+            ModifierButton::ReleasedJump(_) => 2,
+            ModifierButton::Attack => 3,
+            ModifierButton::Heal => 4,
+            ModifierButton::Parry => 5,
+            ModifierButton::ChargedAttack => 6,
+            ModifierButton::Ability1 => 7,
+            ModifierButton::Ability2 => 8,
+            ModifierButton::Ability3 => 9,
+            ModifierButton::CastSpell => 10,
+        };
+        1 << idx
+    }
+
+    /// How long a press stays in the `InputBuffer` by default.
+    fn default_window(self) -> f64 {
+        match self {
+            ModifierButton::Jump | ModifierButton::Attack => 0.15,
+            _ => 0.1,
+        }
+    }
+}
+
+/// Player inputs, used by the state machine. Any number of action buttons can be active at once.
 #[derive(Default, Clone, PartialEq, Debug, Copy)]
-pub struct Inputs(
-    pub Option<MoveButton>,
-    pub Option<ModifierButton>,
-    pub Option<ModifierButton>,
-);
+pub struct Inputs {
+    pub movement: Option<MoveButton>,
+    /// How far an analog stick is tilted when moving left or right, rounded to eighths so small
+    /// wobbles don't count as new input. `None` for digital input, which always moves at full
+    /// speed.
+    pub tilt: Option<f32>,
+    actions: u16,
+    released_jump: Option<f32>,
+}
+
+impl Inputs {
+    pub fn new(movement: Option<MoveButton>, actions: &[ModifierButton]) -> Self {
+        actions.iter().fold(
+            Self {
+                movement,
+                ..Default::default()
+            },
+            |inputs, action| inputs.with(*action),
+        )
+    }
+
+    /// Returns a copy of these inputs with `action` active.
+    pub fn with(mut self, action: ModifierButton) -> Self {
+        self.actions |= action.bit();
+        if let ModifierButton::ReleasedJump(time) = action {
+            self.released_jump = Some(time);
+        }
+        self
+    }
+
+    pub fn has(&self, action: ModifierButton) -> bool {
+        self.actions & action.bit() != 0
+    }
+
+    /// How long jump was held for, if it was released this frame.
+    pub fn released_jump(&self) -> Option<f32> {
+        self.released_jump
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct BufferedAction {
+    action: ModifierButton,
+    time: f64,
+}
+
+/// Recently pressed actions with the time they were pressed. Lets an action pressed slightly too
+/// early, like jumping just before landing, still be performed once it becomes possible.
+#[derive(Clone, Debug)]
+pub struct InputBuffer {
+    now: f64,
+    presses: VecDeque<BufferedAction>,
+    /// Per action overrides of how long a press is kept, keyed by `ModifierButton::bit`.
+    windows: HashMap<u16, f64>,
+}
+
+impl Default for InputBuffer {
+    fn default() -> Self {
+        Self {
+            now: 0.0,
+            presses: VecDeque::new(),
+            windows: HashMap::new(),
+        }
+    }
+}
+
+impl InputBuffer {
+    /// How long a press of `action` stays in the buffer.
+    pub fn window(&self, action: ModifierButton) -> f64 {
+        self.windows
+            .get(&action.bit())
+            .copied()
+            .unwrap_or_else(|| action.default_window())
+    }
+
+    pub fn set_window(&mut self, action: ModifierButton, secs: f64) {
+        self.windows.insert(action.bit(), secs);
+    }
+
+    /// Advances the buffer clock and drops presses older than their window.
+    pub fn advance(&mut self, delta: f64) {
+        self.now += delta;
+        let now = self.now;
+        let windows = &self.windows;
+        self.presses.retain(|p| {
+            let window = windows
+                .get(&p.action.bit())
+                .copied()
+                .unwrap_or_else(|| p.action.default_window());
+            now - p.time <= window
+        });
+    }
+
+    pub fn push(&mut self, action: ModifierButton) {
+        self.presses.push_back(BufferedAction {
+            action,
+            time: self.now,
+        });
+    }
+
+    /// Removes the most recent press of `action` still within its window. Returns whether there
+    /// was one.
+    pub fn consume(&mut self, action: ModifierButton) -> bool {
+        let window = self.window(action);
+        let now = self.now;
+        match self
+            .presses
+            .iter()
+            .rposition(|p| p.action.bit() == action.bit() && now - p.time <= window)
+        {
+            Some(idx) => {
+                self.presses.remove(idx);
+                true
+            }
+            None => false,
+        }
+    }
+}
 
 #[derive(Default, Clone)]
 pub struct InputHandler;
 
 impl InputHandler {
-    pub fn get_movement(input: &Gd<Input>) -> Inputs {
-        let mut inputs = Inputs::default();
-        if input.is_action_pressed("east") {
-            inputs.0 = Some(MoveButton::Right);
-        } else if input.is_action_pressed("west") {
-            inputs.0 = Some(MoveButton::Left);
+    pub fn get_movement(input: &InputState) -> Inputs {
+        let move_x = input.move_x();
+        let movement = if input.is_pressed(Action::South) {
+            Some(MoveButton::Down)
+        } else if move_x > 0.0 {
+            Some(MoveButton::Right)
+        } else if move_x < 0.0 {
+            Some(MoveButton::Left)
+        } else if input.is_pressed(Action::North) {
+            Some(MoveButton::Up)
         } else {
-            inputs.0 = None;
+            None
+        };
+        let mut inputs = Inputs::new(movement, &[]);
+        if matches!(movement, Some(MoveButton::Left | MoveButton::Right)) {
+            let tilt = ((move_x.abs() * 8.0).ceil() / 8.0).min(1.0);
+            inputs.tilt = (tilt < 1.0).then_some(tilt);
         }
         inputs
     }
 
-    pub fn handle(input: &Gd<Input>, player: &mut MainCharacter) -> Inputs {
+    pub fn handle(input: &InputState) -> Inputs {
         let mut inputs = Self::get_movement(input);
-
-        if input.is_action_just_pressed("attack") {
-            if inputs.1.is_some() {
-                inputs.2 = Some(ModifierButton::Attack);
-            } else {
-                inputs.1 = Some(ModifierButton::Attack);
+        for action in Action::GAMEPLAY {
+            let Some(button) = action.modifier() else {
+                continue;
+            };
+            let active = match button {
+                // Held actions.
+                ModifierButton::Jump
+                | ModifierButton::Dodge
+                | ModifierButton::Parry
+                | ModifierButton::Ability2
+                | ModifierButton::Ability3 => input.is_pressed(action),
+                _ => input.is_just_pressed(action),
+            };
+            if active {
+                inputs = inputs.with(button);
             }
         }
-        if input.is_action_pressed("attack") {
-            let delta = player.base().get_physics_process_delta_time() as f32;
-
-            // Safety: Only used on the Main thread.
-            unsafe {
-                if CHARGE_ATTACK_TIME < 2.0 {
-                    CHARGE_ATTACK_TIME += delta;
-                }
-            }
+        if input.is_just_released(Action::Jump) {
+            inputs = inputs.with(ModifierButton::ReleasedJump(input.held_time(Action::Jump)));
         }
-        if input.is_action_pressed("jump") {
-            let delta = player.base().get_physics_process_delta_time() as f32;
-
-            // Safety: Only used on the Main thread.
-            unsafe {
-                JUMPING_TIME += delta;
-            }
-            if inputs.1.is_some() {
-                inputs.2 = Some(ModifierButton::Jump);
-            } else {
-                inputs.1 = Some(ModifierButton::Jump);
-            }
-        }
-        if input.is_action_just_pressed("ability_1") {
-            if inputs.1.is_some() {
-                inputs.2 = Some(ModifierButton::Ability1);
-            } else {
-                inputs.1 = Some(ModifierButton::Ability1);
-            }
-        } else if input.is_action_pressed("ability_2") {
-            if inputs.1.is_some() {
-                inputs.2 = Some(ModifierButton::Ability2);
-            } else {
-                inputs.1 = Some(ModifierButton::Ability2);
-            }
-        } else if input.is_action_pressed("ability_3") {
-            if inputs.1.is_some() {
-                inputs.2 = Some(ModifierButton::Ability3);
-            } else {
-                inputs.1 = Some(ModifierButton::Ability3);
-            }
-        } else if input.is_action_just_released("jump") {
-            // Safety: Only used on the Main thread.
-            unsafe {
-                inputs.1 = Some(ModifierButton::ReleasedJump(JUMPING_TIME));
-                JUMPING_TIME = 0.0;
-            }
-        } else if input.is_action_just_released("attack") {
-            // Safety: Only used on the Main thread.
-            unsafe {
-                if CHARGE_ATTACK_TIME >= 2.0 {
-                    inputs.1 = Some(ModifierButton::ChargedAttack);
-                    CHARGE_ATTACK_TIME = 0.0;
-                } else {
-                    CHARGE_ATTACK_TIME = 0.0;
-                }
-            }
-        } else if input.is_action_pressed("dodge") {
-            inputs.1 = Some(ModifierButton::Dodge);
-        } else if input.is_action_just_pressed("heal") {
-            inputs.1 = Some(ModifierButton::Heal);
-        } else if input.is_action_pressed("parry") {
-            {
-                inputs.1 = Some(ModifierButton::Parry);
-            }
+        if input.is_just_released(Action::Attack) && input.charge_progress() >= 1.0 {
+            inputs = inputs.with(ModifierButton::ChargedAttack);
         }
         inputs
     }
@@ -142,9 +546,9 @@ pub struct DevInputHandler;
 
 impl DevInputHandler {
     pub fn handle_unhandled(event: &Gd<Input>, entity: &mut MainCharacter) -> Inputs {
-        // let inputs = InputHandler::handle(&Input::singleton(), entity);
-        let inputs = InputHandler::handle(event, entity);
-        if event.is_action_pressed("dev_teleport") {
+        let inputs = InputHandler::handle(&entity.input_state);
+        // Teleporting would desync a replay.
+        if event.is_action_pressed("dev_teleport") && !entity.replay.is_replaying() {
             let pos = entity
                 .base()
                 .get_viewport()
@@ -155,6 +559,24 @@ impl DevInputHandler {
             entity.base_mut().set_global_position(pos);
         }
 
+        if event.is_action_just_pressed("dev_reload_tuning") && Os::singleton().is_debug_build() {
+            MovementTuning::reload();
+            println!("DevTools: Reloaded movement tuning");
+        }
+
+        if event.is_action_just_pressed("dev_toggle_recording") && Os::singleton().is_debug_build()
+        {
+            Self::report(entity.toggle_recording());
+        }
+
+        if event.is_action_just_pressed("dev_replay") && Os::singleton().is_debug_build() {
+            Self::report(entity.start_replay());
+        }
+
+        if event.is_action_just_pressed("dev_dump_history") && Os::singleton().is_debug_build() {
+            Self::report(entity.dump_sm_history());
+        }
+
         if event.is_action_just_pressed("dev_increase_level") {
             entity.stats.get_mut(Stat::Level).0 += 1;
             println!(
@@ -174,11 +596,21 @@ impl DevInputHandler {
         }
         inputs
     }
+
+    /// Prints the outcome of a dev command run from its hotkey instead of the console.
+    fn report(result: Result<String, String>) {
+        match result {
+            Ok(message) => println!("DevTools: {message}"),
+            Err(message) => godot_warn!("{message}"),
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::utils::input_hanlder::Inputs;
+    use crate::utils::input_hanlder::{
+        Action, InputFrame, InputHandler, InputState, Inputs, ModifierButton, MoveButton,
+    };
 
     static mut JUMP_TIME: f32 = 0.0;
 
@@ -194,10 +626,9 @@ mod test {
             }
         }
         let input = unsafe {
-            Inputs(
+            Inputs::new(
                 Some(super::MoveButton::Left),
-                Some(super::ModifierButton::ReleasedJump(JUMP_TIME)),
-                None,
+                &[super::ModifierButton::ReleasedJump(JUMP_TIME)],
             )
         };
 
@@ -207,4 +638,70 @@ mod test {
     fn test_receiving(input: Inputs) {
         dbg!(input);
     }
+
+    #[test]
+    fn test_input_state_edges_and_hold_time() {
+        let mut state = InputState::default();
+        state.update(&InputFrame::new(&[Action::Jump, Action::East]), 0.25);
+        assert!(state.is_just_pressed(Action::Jump));
+        assert_eq!(
+            InputHandler::handle(&state),
+            Inputs::new(Some(MoveButton::Right), &[ModifierButton::Jump])
+        );
+
+        state.update(&InputFrame::new(&[Action::Jump]), 0.25);
+        state.update(&InputFrame::new(&[Action::Jump]), 0.25);
+        assert!(!state.is_just_pressed(Action::Jump));
+
+        state.update(&InputFrame::default(), 0.25);
+        assert_eq!(
+            InputHandler::handle(&state),
+            Inputs::new(None, &[ModifierButton::ReleasedJump(0.75)])
+        );
+    }
+
+    #[test]
+    fn test_input_state_charged_attack() {
+        let mut state = InputState::default();
+        for _ in 0..8 {
+            state.update(&InputFrame::new(&[Action::Attack]), 0.25);
+        }
+        assert_eq!(state.charge_progress(), 1.0);
+        state.update(&InputFrame::default(), 0.25);
+        assert!(InputHandler::handle(&state).has(ModifierButton::ChargedAttack));
+    }
+
+    #[test]
+    fn test_analog_movement_deadzone() {
+        let mut state = InputState::default();
+        let mut tilt = |x: f32| {
+            state.update(&InputFrame::from_parts(0, x), 0.25);
+            let inputs = InputHandler::handle(&state);
+            (inputs.movement, inputs.tilt)
+        };
+        assert_eq!(tilt(0.1), (None, None));
+        assert_eq!(tilt(-0.3), (Some(MoveButton::Left), Some(0.25)));
+        assert_eq!(tilt(0.95), (Some(MoveButton::Right), None));
+    }
+
+    #[test]
+    fn test_input_buffer_windows_and_consume() {
+        let mut state = InputState::default();
+        state.update(&InputFrame::new(&[Action::Jump, Action::Attack]), 0.05);
+        let inputs = InputHandler::handle(&state);
+        assert!(inputs.has(ModifierButton::Jump) && inputs.has(ModifierButton::Attack));
+
+        state.update(&InputFrame::default(), 0.1);
+        assert!(state.buffer.consume(ModifierButton::Jump));
+        assert!(!state.buffer.consume(ModifierButton::Jump));
+
+        // Attack's window is 0.15s, so the press expires on the next frame.
+        state.update(&InputFrame::default(), 0.1);
+        assert!(!state.buffer.consume(ModifierButton::Attack));
+
+        state.buffer.set_window(ModifierButton::Dodge, 1.0);
+        state.update(&InputFrame::new(&[Action::Dodge]), 0.5);
+        state.update(&InputFrame::default(), 0.5);
+        assert!(state.buffer.consume(ModifierButton::Dodge));
+    }
 }
@@ -0,0 +1,258 @@
+use godot::{
+    builtin::{GString, Vector2},
+    classes::{FileAccess, file_access::ModeFlags},
+};
+
+use crate::utils::input_hanlder::{InputFrame, InputSource};
+
+pub const REPLAY_PATH: &str = "user://input.replay";
+
+/// Hashes the player's position and state so a replay can detect when it diverges from the
+/// recording. Uses FNV-1a so checksums stay stable between builds.
+pub fn checksum(position: Vector2, state: &str) -> u64 {
+    let bytes = position
+        .x
+        .to_bits()
+        .to_le_bytes()
+        .into_iter()
+        .chain(position.y.to_bits().to_le_bytes())
+        .chain(state.bytes());
+    bytes.fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RecordedFrame {
+    pub delta: f32,
+    pub input: InputFrame,
+    /// Checksum of the player after the frame was processed.
+    pub checksum: u64,
+}
+
+/// Player input for every physics frame, with what's needed to start a replay from the same
+/// point as the recording.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InputRecording {
+    /// Seed of Godot's global RNG when recording started.
+    pub seed: i64,
+    pub start_position: Vector2,
+    pub start_state: String,
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl InputRecording {
+    pub fn new(seed: i64, start_position: Vector2, start_state: String) -> Self {
+        Self {
+            seed,
+            start_position,
+            start_state,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Serializes the recording as a header followed by one `delta buttons stick checksum` line
+    /// per frame.
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "seed {}\nstart {} {} {}\n",
+            self.seed, self.start_position.x, self.start_position.y, self.start_state
+        );
+        for frame in &self.frames {
+            text.push_str(&format!(
+                "{} {} {} {}\n",
+                frame.delta,
+                frame.input.bits(),
+                frame.input.move_x(),
+                frame.checksum
+            ));
+        }
+        text
+    }
+
+    pub fn from_text(text: &str) -> Option<Self> {
+        let mut lines = text.lines();
+        let seed = lines.next()?.strip_prefix("seed ")?.parse().ok()?;
+        let mut start = lines.next()?.strip_prefix("start ")?.split(' ');
+        let x = start.next()?.parse().ok()?;
+        let y = start.next()?.parse().ok()?;
+        let start_state = start.next()?.to_string();
+
+        let mut recording = Self::new(seed, Vector2::new(x, y), start_state);
+        for line in lines.filter(|l| !l.is_empty()) {
+            let mut parts = line.split(' ');
+            recording.frames.push(RecordedFrame {
+                delta: parts.next()?.parse().ok()?,
+                input: InputFrame::from_parts(
+                    parts.next()?.parse().ok()?,
+                    parts.next()?.parse().ok()?,
+                ),
+                checksum: parts.next()?.parse().ok()?,
+            });
+        }
+        Some(recording)
+    }
+
+    /// Writes the recording to `user://`.
+    pub fn save(&self) -> Result<(), ()> {
+        let mut file = FileAccess::open(REPLAY_PATH, ModeFlags::WRITE).ok_or(())?;
+        file.store_string(&self.to_text());
+        Ok(())
+    }
+
+    pub fn load() -> Option<Self> {
+        let text: GString = FileAccess::get_file_as_string(REPLAY_PATH);
+        Self::from_text(&text.to_string())
+    }
+}
+
+/// The frame a replay first diverged from its recording.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Desync {
+    pub frame: usize,
+}
+
+/// Whether the player's input is live, being recorded, or fed back from a recording.
+#[derive(Default, Clone, Debug)]
+pub enum InputReplay {
+    #[default]
+    Off,
+    Recording(InputRecording),
+    Replaying {
+        recording: InputRecording,
+        frame: usize,
+        desynced: bool,
+    },
+}
+
+impl InputReplay {
+    /// The input and delta to use this frame. While replaying these come from the recording
+    /// instead of `live`. Replays switch back to live input once they run out of frames.
+    pub fn begin_frame(&mut self, live: &impl InputSource, delta: f32) -> (InputFrame, f32) {
+        match self {
+            InputReplay::Replaying {
+                recording, frame, ..
+            } => match recording.frames.get(*frame) {
+                Some(recorded) => (recorded.input, recorded.delta),
+                None => {
+                    *self = InputReplay::Off;
+                    (InputFrame::capture(live), delta)
+                }
+            },
+            _ => (InputFrame::capture(live), delta),
+        }
+    }
+
+    /// Records the frame, or checks it against the recording while replaying. Only the first
+    /// desync of a replay is reported.
+    pub fn end_frame(
+        &mut self,
+        input: InputFrame,
+        delta: f32,
+        checksum: u64,
+    ) -> Result<(), Desync> {
+        match self {
+            InputReplay::Off => Ok(()),
+            InputReplay::Recording(recording) => {
+                recording.frames.push(RecordedFrame {
+                    delta,
+                    input,
+                    checksum,
+                });
+                Ok(())
+            }
+            InputReplay::Replaying {
+                recording,
+                frame,
+                desynced,
+            } => {
+                let expected = recording.frames.get(*frame).map(|f| f.checksum);
+                let idx = *frame;
+                *frame += 1;
+                if !*desynced && expected.is_some_and(|c| c != checksum) {
+                    *desynced = true;
+                    Err(Desync { frame: idx })
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        matches!(self, InputReplay::Replaying { .. })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use godot::builtin::Vector2;
+
+    use crate::utils::{
+        input_hanlder::{Action, InputFrame},
+        input_replay::{Desync, InputRecording, InputReplay, checksum},
+    };
+
+    #[test]
+    fn test_recording_text_round_trip() {
+        let mut replay = InputReplay::Recording(InputRecording::new(
+            42,
+            Vector2::new(10.5, -3.25),
+            "idle".to_string(),
+        ));
+        let frames = [
+            InputFrame::new(&[Action::Jump, Action::East]),
+            InputFrame::default(),
+        ];
+        for (i, input) in frames.into_iter().enumerate() {
+            let pos = Vector2::new(i as f32 * 0.1, 0.0);
+            replay
+                .end_frame(input, 1.0 / 60.0, checksum(pos, "run"))
+                .unwrap();
+        }
+        let InputReplay::Recording(recording) = replay else {
+            unreachable!()
+        };
+        assert_eq!(
+            InputRecording::from_text(&recording.to_text()),
+            Some(recording)
+        );
+    }
+
+    #[test]
+    fn test_replay_detects_desync() {
+        let mut recording = InputRecording::new(0, Vector2::ZERO, "idle".to_string());
+        let mut recorder = InputReplay::Recording(recording.clone());
+        for x in 0..3 {
+            let input = InputFrame::new(&[Action::West]);
+            recorder
+                .end_frame(input, 0.5, checksum(Vector2::new(x as f32, 0.0), "run"))
+                .unwrap();
+        }
+        if let InputReplay::Recording(r) = recorder {
+            recording = r;
+        }
+
+        let mut replay = InputReplay::Replaying {
+            recording,
+            frame: 0,
+            desynced: false,
+        };
+        let live = InputFrame::default();
+        let (input, delta) = replay.begin_frame(&live, 0.1);
+        assert_eq!((input, delta), (InputFrame::new(&[Action::West]), 0.5));
+        assert!(
+            replay
+                .end_frame(input, delta, checksum(Vector2::ZERO, "run"))
+                .is_ok()
+        );
+        assert_eq!(
+            replay.end_frame(input, delta, checksum(Vector2::ZERO, "run")),
+            Err(Desync { frame: 1 })
+        );
+        // Later mismatches aren't reported again.
+        assert!(replay.end_frame(input, delta, 0).is_ok());
+        replay.begin_frame(&live, 0.1);
+        assert!(!replay.is_replaying());
+    }
+}
@@ -1,6 +1,13 @@
 pub mod collision_layers;
+pub mod console_commands;
 #[allow(unused)]
 pub mod constants;
 pub mod global_data_singleton;
+pub mod input_bindings;
+pub mod input_device;
 pub mod input_hanlder;
+pub mod input_replay;
 pub mod node_utils;
+pub mod save_data;
+pub mod sm_history;
+pub mod time_control;
@@ -0,0 +1,156 @@
+use godot::{
+    builtin::{VarArray, VarDictionary, Vector2},
+    classes::ConfigFile,
+    global::Error,
+    meta::ToGodot,
+    obj::NewGd,
+};
+
+use crate::{
+    entities::player::abilities::MovementAbilities,
+    utils::global_data_singleton::GlobalData,
+    world::{
+        checkpoint::CheckpointData,
+        world_state::{ObjectState, WorldState},
+    },
+};
+
+const SAVE_PATH: &str = "user://save.cfg";
+
+/// Game progress that persists between sessions.
+#[derive(Default, Clone, Debug)]
+pub struct SaveData {
+    pub checkpoint: Option<CheckpointData>,
+    pub world_state: WorldState,
+    pub abilities: MovementAbilities,
+}
+
+impl SaveData {
+    /// Collects the current progress from the `GlobalData` singleton.
+    pub fn from_global_data(data: &GlobalData) -> Self {
+        Self {
+            checkpoint: data.checkpoint.clone(),
+            world_state: data.world_state.clone(),
+            abilities: data
+                .player
+                .as_ref()
+                .map(|p| p.bind().abilities)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Writes the save file to `user://`.
+    pub fn save(&self) -> Result<(), Error> {
+        let mut file = ConfigFile::new_gd();
+        if let Some(checkpoint) = &self.checkpoint {
+            file.set_value("checkpoint", "map", &checkpoint.map_scene.to_variant());
+            file.set_value("checkpoint", "position", &checkpoint.position.to_variant());
+        }
+
+        file.set_value(
+            "abilities",
+            "double_jump",
+            &self.abilities.double_jump.to_variant(),
+        );
+        file.set_value(
+            "abilities",
+            "air_dash",
+            &self.abilities.air_dash.to_variant(),
+        );
+        file.set_value(
+            "abilities",
+            "wall_grab",
+            &self.abilities.wall_grab.to_variant(),
+        );
+
+        let mut objects = VarArray::new();
+        for (key, state) in self.world_state.iter() {
+            let (kind, value) = state.to_parts();
+            let mut object = VarDictionary::new();
+            object.set("map", key.map_id.as_str());
+            object.set("object", key.object_id.as_str());
+            object.set("state", kind);
+            object.set("value", value);
+            objects.push(&object.to_variant());
+        }
+        file.set_value("world_state", "objects", &objects.to_variant());
+
+        match file.save(SAVE_PATH) {
+            Error::OK => Ok(()),
+            e => Err(e),
+        }
+    }
+
+    /// Reads the save file from `user://`, if one exists.
+    pub fn load() -> Option<Self> {
+        let mut file = ConfigFile::new_gd();
+        if file.load(SAVE_PATH) != Error::OK {
+            return None;
+        }
+
+        let checkpoint = if file.has_section_key("checkpoint", "map") {
+            Some(CheckpointData {
+                map_scene: file.get_value("checkpoint", "map").to::<String>(),
+                position: file
+                    .get_value_ex("checkpoint", "position")
+                    .default(&Vector2::ZERO.to_variant())
+                    .done()
+                    .to::<Vector2>(),
+            })
+        } else {
+            None
+        };
+
+        let ability = |key: &str| {
+            file.get_value_ex("abilities", key)
+                .default(&false.to_variant())
+                .done()
+                .try_to::<bool>()
+                .unwrap_or_default()
+        };
+        let abilities = MovementAbilities {
+            double_jump: ability("double_jump"),
+            air_dash: ability("air_dash"),
+            wall_grab: ability("wall_grab"),
+        };
+
+        let mut world_state = WorldState::default();
+        let objects = file
+            .get_value_ex("world_state", "objects")
+            .default(&VarArray::new().to_variant())
+            .done()
+            .try_to::<VarArray>()
+            .unwrap_or_default();
+        for object in objects.iter_shared() {
+            let Ok(object) = object.try_to::<VarDictionary>() else {
+                continue;
+            };
+            let field = |k: &str| object.get(k).unwrap_or_default();
+            let state = ObjectState::from_parts(
+                &field("state").to::<String>(),
+                field("value").try_to::<i64>().unwrap_or_default(),
+            );
+            if let Some(state) = state {
+                world_state.record(
+                    &field("map").to::<String>(),
+                    &field("object").to::<String>(),
+                    state,
+                );
+            }
+        }
+
+        Some(Self {
+            checkpoint,
+            world_state,
+            abilities,
+        })
+    }
+
+    /// Saves the current progress held by the `GlobalData` singleton.
+    pub fn save_progress() {
+        let data = Self::from_global_data(&GlobalData::singleton().bind());
+        if let Err(e) = data.save() {
+            godot::global::godot_warn!("Failed to save progress: {e:?}");
+        }
+    }
+}
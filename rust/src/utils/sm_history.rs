@@ -0,0 +1,145 @@
+use std::{collections::VecDeque, fmt::Debug};
+
+use godot::{
+    classes::{FileAccess, Node, file_access::ModeFlags},
+    obj::Gd,
+};
+
+use crate::{
+    entities::enemies::{enemy_body_actor::EnemyBodyActor, projectile_enemy::NewProjectileEnemy},
+    utils::global_data_singleton::GlobalData,
+};
+
+pub const HISTORY_PATH: &str = "user://sm_history.log";
+
+/// How many transitions each state machine remembers.
+const CAPACITY: usize = 64;
+
+/// A state change, with the event that caused it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Transition {
+    /// Physics frame the transition happened on.
+    pub frame: u64,
+    pub from: String,
+    pub event: String,
+    pub to: String,
+}
+
+impl std::fmt::Display for Transition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{}] {} --{}--> {}",
+            self.frame, self.from, self.event, self.to
+        )
+    }
+}
+
+/// The most recent transitions of a state machine, oldest first. Events that leave the state
+/// unchanged aren't recorded.
+#[derive(Clone, Debug, Default)]
+pub struct TransitionHistory {
+    transitions: VecDeque<Transition>,
+}
+
+impl TransitionHistory {
+    pub fn record<S: Debug + PartialEq, E: Debug>(
+        &mut self,
+        frame: u64,
+        from: &S,
+        event: &E,
+        to: &S,
+    ) {
+        if from == to {
+            return;
+        }
+        if self.transitions.len() == CAPACITY {
+            self.transitions.pop_front();
+        }
+        self.transitions.push_back(Transition {
+            frame,
+            from: format!("{from:?}"),
+            event: format!("{event:?}"),
+            to: format!("{to:?}"),
+        });
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Transition> {
+        self.transitions.iter()
+    }
+
+    /// The last `n` transitions, newest first.
+    pub fn latest(&self, n: usize) -> impl Iterator<Item = &Transition> {
+        self.transitions.iter().rev().take(n)
+    }
+}
+
+/// A snapshot of one entity's state machine, for the debug overlay and dumps.
+#[derive(Clone, Debug)]
+pub struct EntityHistory {
+    pub name: String,
+    pub state: String,
+    pub history: TransitionHistory,
+}
+
+/// The histories of the enemies in the current map. `node` can be any node in the tree.
+pub fn enemy_histories(node: &Gd<Node>) -> Vec<EntityHistory> {
+    let Some(map) = GlobalData::singleton().bind().paths.map.clone() else {
+        return Vec::new();
+    };
+    let Some(enemies) = node.try_get_node_as::<Node>(&format!("{map}/Enemies")) else {
+        return Vec::new();
+    };
+    enemies
+        .get_children()
+        .iter_shared()
+        .filter_map(|node| {
+            if let Ok(enemy) = node.clone().try_cast::<EnemyBodyActor>() {
+                Some(enemy.bind().sm_history())
+            } else if let Ok(enemy) = node.try_cast::<NewProjectileEnemy>() {
+                Some(enemy.bind().sm_history())
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Writes the histories to `user://`, replacing the last dump.
+pub fn dump(histories: &[EntityHistory]) -> Result<(), ()> {
+    let mut text = String::new();
+    for entity in histories {
+        text.push_str(&format!("== {} ({})\n", entity.name, entity.state));
+        for transition in entity.history.iter() {
+            text.push_str(&format!("{transition}\n"));
+        }
+        text.push('\n');
+    }
+    let mut file = FileAccess::open(HISTORY_PATH, ModeFlags::WRITE).ok_or(())?;
+    file.store_string(&text);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::utils::sm_history::{CAPACITY, TransitionHistory};
+
+    #[test]
+    fn test_history_is_a_ring_buffer_of_changes() {
+        let mut history = TransitionHistory::default();
+        history.record(1, &"idle", &"Tick", &"idle");
+        assert_eq!(history.iter().count(), 0);
+
+        for frame in 0..CAPACITY as u64 + 5 {
+            history.record(frame, &"idle", &"Jump", &"jumping");
+        }
+        assert_eq!(history.iter().count(), CAPACITY);
+        assert_eq!(history.iter().next().unwrap().frame, 5);
+        let newest = history.latest(1).next().unwrap();
+        assert_eq!(newest.frame, CAPACITY as u64 + 4);
+        assert_eq!(
+            newest.to_string(),
+            format!("[{}] \"idle\" --\"Jump\"--> \"jumping\"", newest.frame)
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+
+use godot::{
+    classes::{AnimationPlayer, Engine, Node, Time, Timer},
+    prelude::*,
+};
+
+use crate::utils::global_data_singleton::GlobalData;
+
+/// Physics frames the attacker and target freeze for when a hit lands.
+pub const HITSTOP_FRAMES: u64 = 4;
+
+/// A global slow down, lasting `seconds` of real time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SlowMotion {
+    pub scale: f64,
+    pub seconds: f64,
+}
+
+pub const PERFECT_PARRY_SLOW_MOTION: SlowMotion = SlowMotion {
+    scale: 0.3,
+    seconds: 0.5,
+};
+
+pub const BOSS_KILL_SLOW_MOTION: SlowMotion = SlowMotion {
+    scale: 0.2,
+    seconds: 1.5,
+};
+
+/// Hitstop and slow motion requests. Held by `GlobalData` and ticked by `Main` every physics
+/// frame.
+///
+/// Requests can overlap:
+/// - Hitstops on the same entity don't stack, the entity thaws when the latest one ends.
+/// - Each slow motion ends on its own, the slowest active one decides the time scale.
+/// - The base scale set from the dev console applies on top of slow motion.
+#[derive(Clone, Debug)]
+pub struct TimeControl {
+    /// Frozen entities, and their animation players, with the physics frame they thaw on.
+    frozen: HashMap<InstanceId, u64>,
+    /// Active slow motion and when it ends, in real seconds.
+    slow_motion: Vec<(f64, f64)>,
+    base_scale: f64,
+}
+
+impl Default for TimeControl {
+    fn default() -> Self {
+        Self {
+            frozen: HashMap::new(),
+            slow_motion: Vec::new(),
+            base_scale: 1.0,
+        }
+    }
+}
+
+impl TimeControl {
+    /// Freezes `id` until `frame + frames`. Returns `true` if it wasn't already frozen.
+    pub fn freeze(&mut self, id: InstanceId, frames: u64, frame: u64) -> bool {
+        let end = frame + frames;
+        match self.frozen.get_mut(&id) {
+            Some(thaw) => {
+                *thaw = (*thaw).max(end);
+                false
+            }
+            None => {
+                self.frozen.insert(id, end);
+                true
+            }
+        }
+    }
+
+    pub fn is_frozen(&self, id: InstanceId) -> bool {
+        self.frozen.contains_key(&id)
+    }
+
+    /// Starts slowing time down at `now`, in real seconds.
+    pub fn slow_down(&mut self, slow_motion: SlowMotion, now: f64) {
+        self.slow_motion
+            .push((slow_motion.scale, now + slow_motion.seconds));
+    }
+
+    pub fn set_base_scale(&mut self, scale: f64) {
+        self.base_scale = scale;
+    }
+
+    /// Ends finished requests, returning the entities that thawed.
+    pub fn tick(&mut self, frame: u64, now: f64) -> Vec<InstanceId> {
+        self.slow_motion.retain(|(_, end)| *end > now);
+        let thawed = self
+            .frozen
+            .iter()
+            .filter(|(_, thaw)| **thaw <= frame)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        for id in &thawed {
+            self.frozen.remove(id);
+        }
+        thawed
+    }
+
+    /// The engine's time scale for the active requests.
+    pub fn time_scale(&self) -> f64 {
+        let slowest = self
+            .slow_motion
+            .iter()
+            .map(|(scale, _)| *scale)
+            .fold(1.0, f64::min);
+        self.base_scale * slowest
+    }
+}
+
+/// Freezes each node's processing, timers and animations for `frames` physics frames.
+pub fn hitstop(nodes: &[Gd<Node>], frames: u64) {
+    let frame = Engine::singleton().get_physics_frames();
+    let freeze = |id| {
+        GlobalData::singleton()
+            .bind_mut()
+            .time_control
+            .freeze(id, frames, frame)
+    };
+    for node in nodes {
+        if freeze(node.instance_id()) {
+            set_timers_paused(node, true);
+        }
+        // Animation players are frozen on their own, so only those that were playing resume.
+        for child in node.get_children().iter_shared() {
+            if let Ok(mut anim) = child.try_cast::<AnimationPlayer>()
+                && (anim.is_playing() || is_frozen(&anim.clone().upcast()))
+                && freeze(anim.instance_id())
+            {
+                anim.pause();
+            }
+        }
+    }
+}
+
+/// Sets the time scale applied on top of slow motion.
+pub fn set_base_scale(scale: f64) {
+    GlobalData::singleton()
+        .bind_mut()
+        .time_control
+        .set_base_scale(scale);
+    apply_time_scale();
+}
+
+pub fn slow_motion(slow_motion: SlowMotion) {
+    GlobalData::singleton()
+        .bind_mut()
+        .time_control
+        .slow_down(slow_motion, now());
+    apply_time_scale();
+}
+
+/// Whether `node` is in hitstop. Frozen entities skip their movement and resource ticks.
+pub fn is_frozen(node: &Gd<Node>) -> bool {
+    GlobalData::singleton()
+        .bind()
+        .time_control
+        .is_frozen(node.instance_id())
+}
+
+/// Thaws entities and updates the time scale for finished requests.
+pub fn tick() {
+    let frame = Engine::singleton().get_physics_frames();
+    let thawed = GlobalData::singleton()
+        .bind_mut()
+        .time_control
+        .tick(frame, now());
+    for id in thawed {
+        if let Ok(mut anim) = Gd::<AnimationPlayer>::try_from_instance_id(id) {
+            anim.play();
+        } else if let Ok(node) = Gd::<Node>::try_from_instance_id(id) {
+            set_timers_paused(&node, false);
+        }
+    }
+    apply_time_scale();
+}
+
+fn apply_time_scale() {
+    let scale = GlobalData::singleton().bind().time_control.time_scale();
+    if Engine::singleton().get_time_scale() != scale {
+        Engine::singleton().set_time_scale(scale);
+    }
+}
+
+/// Seconds since the engine started, unaffected by the time scale.
+fn now() -> f64 {
+    Time::singleton().get_ticks_usec() as f64 / 1_000_000.0
+}
+
+/// Pauses the node's child `Timer`s.
+fn set_timers_paused(node: &Gd<Node>, paused: bool) {
+    for child in node.get_children().iter_shared() {
+        if let Ok(mut timer) = child.try_cast::<Timer>() {
+            timer.set_paused(paused);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use godot::obj::InstanceId;
+
+    use crate::utils::time_control::{SlowMotion, TimeControl};
+
+    #[test]
+    fn test_overlapping_requests() {
+        let mut time = TimeControl::default();
+        let id = InstanceId::from_i64(1);
+        assert!(time.freeze(id, 4, 10));
+        assert!(!time.freeze(id, 2, 11));
+        assert!(time.tick(13, 0.0).is_empty());
+        assert!(time.is_frozen(id));
+        assert_eq!(time.tick(14, 0.0), vec![id]);
+        assert!(!time.is_frozen(id));
+
+        time.set_base_scale(0.5);
+        let slow = |scale, seconds| SlowMotion { scale, seconds };
+        time.slow_down(slow(0.3, 1.0), 0.0);
+        time.slow_down(slow(0.6, 2.0), 0.5);
+        time.tick(20, 0.9);
+        assert_eq!(time.time_scale(), 0.5 * 0.3);
+        time.tick(21, 1.0);
+        assert_eq!(time.time_scale(), 0.5 * 0.6);
+        time.tick(22, 2.5);
+        assert_eq!(time.time_scale(), 0.5);
+    }
+}
@@ -0,0 +1,108 @@
+use godot::{
+    classes::{Area2D, IArea2D, InputEvent},
+    obj::WithBaseField,
+    prelude::*,
+};
+
+use super::{environment_trigger::TriggerableEnvObject, map::Map};
+use crate::{
+    entities::hit_reg::Hitbox,
+    utils::{
+        collision_layers::CollisionLayers, global_data_singleton::GlobalData,
+        input_hanlder::Action, save_data::SaveData,
+    },
+};
+
+/// The location the player respawns at.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CheckpointData {
+    /// Scene file path of the map the checkpoint belongs to.
+    pub map_scene: String,
+    pub position: Vector2,
+}
+
+/// A shrine the player can rest at, either by interacting with it or by being activated through an
+/// `EnvironmentTrigger`.
+///
+/// Resting records the respawn point, restores the player's resources and heal charges, respawns
+/// the current map's non-boss enemies and saves progress.
+#[derive(GodotClass)]
+#[class(init, base = Area2D)]
+pub struct Checkpoint {
+    /// Offset from the checkpoint's position where the player respawns.
+    #[export]
+    respawn_offset: Vector2,
+    player_in_range: bool,
+    base: Base<Area2D>,
+}
+
+#[godot_api]
+impl IArea2D for Checkpoint {
+    fn ready(&mut self) {
+        self.base_mut()
+            .set_collision_layer_value(CollisionLayers::WorldEffects as i32, true);
+        self.base_mut()
+            .set_collision_mask_value(CollisionLayers::PlayerHitbox as i32, true);
+        self.signals()
+            .area_entered()
+            .connect_self(Self::on_area_entered);
+        self.signals()
+            .area_exited()
+            .connect_self(Self::on_area_exited);
+    }
+
+    fn unhandled_input(&mut self, input: Gd<InputEvent>) {
+        if self.player_in_range && input.is_action_pressed(Action::Interact.name()) {
+            self.rest();
+        }
+    }
+}
+
+#[godot_api]
+impl Checkpoint {
+    #[signal]
+    pub fn rested();
+
+    fn on_area_entered(&mut self, area: Gd<Area2D>) {
+        if area.try_cast::<Hitbox>().is_ok() {
+            self.player_in_range = true;
+        }
+    }
+
+    fn on_area_exited(&mut self, area: Gd<Area2D>) {
+        if area.try_cast::<Hitbox>().is_ok() {
+            self.player_in_range = false;
+        }
+    }
+
+    pub fn rest(&mut self) {
+        let Some(map_path) = GlobalData::singleton().bind().paths.map.clone() else {
+            godot_warn!("Checkpoint: No map is loaded, unable to rest.");
+            return;
+        };
+        let mut map = self.base().get_node_as::<Map>(&map_path);
+        let position = self.base().get_global_position() + self.respawn_offset;
+        GlobalData::singleton().bind_mut().checkpoint = Some(CheckpointData {
+            map_scene: map.get_scene_file_path().to_string(),
+            position,
+        });
+
+        let player = GlobalData::singleton().bind().player.clone();
+        if let Some(mut player) = player {
+            player.bind_mut().rest();
+        }
+        map.bind_mut().respawn_enemies();
+        SaveData::save_progress();
+
+        self.signals().rested().emit();
+        let sigs = GlobalData::singleton().bind().sigs.clone();
+        sigs.signals().player_rested().emit(&self.to_gd());
+    }
+}
+
+#[godot_dyn]
+impl TriggerableEnvObject for Checkpoint {
+    fn on_activated(&mut self) {
+        self.rest();
+    }
+}
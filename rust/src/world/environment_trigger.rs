@@ -1,4 +1,7 @@
-use crate::utils::collision_layers::CollisionLayers;
+use crate::{
+    utils::collision_layers::CollisionLayers,
+    world::world_state::{ObjectState, record_node_state},
+};
 use godot::{
     classes::{Area2D, CollisionShape2D, IArea2D, IStaticBody2D, Marker2D, StaticBody2D},
     obj::WithBaseField,
@@ -80,13 +83,16 @@ impl EnvironmentTrigger {
         if let Some(t) = self.trigger_ty {
             match t {
                 TriggerDuration::OneShot => {
+                    record_node_state(&self.to_gd(), ObjectState::TriggerConsumed);
                     self.base_mut().queue_free();
                 }
                 TriggerDuration::Limited(mut num) => {
                     num -= 1;
                     if num > 0 {
                         self.trigger_ty.replace(TriggerDuration::Limited(num));
+                        record_node_state(&self.to_gd(), ObjectState::TriggerRemaining(num));
                     } else {
+                        record_node_state(&self.to_gd(), ObjectState::TriggerConsumed);
                         self.base_mut().queue_free();
                     }
                 }
@@ -94,6 +100,13 @@ impl EnvironmentTrigger {
             }
         }
     }
+
+    /// Restores the remaining activations of a Limited trigger.
+    pub fn set_remaining_triggers(&mut self, remaining: u32) {
+        if let Some(TriggerDuration::Limited(_)) = self.trigger_ty {
+            self.trigger_ty.replace(TriggerDuration::Limited(remaining));
+        }
+    }
 }
 
 #[derive(GodotClass)]
@@ -149,9 +162,29 @@ impl IStaticBody2D for ClosingDoor {
     }
 }
 
+impl ClosingDoor {
+    /// Instantly moves the door to its open or closed position.
+    pub fn set_closed(&mut self, closed: bool) {
+        let (x, y) = if closed {
+            (self.closed_position.x, self.closed_position.y)
+        } else {
+            (self.open_position.x, self.open_position.y)
+        };
+        self.is_closed = closed;
+        self.base_mut().set_process(false);
+        self.base_mut().set_position(Vector2::new(x, y));
+        let mut shape = self
+            .base()
+            .get_node_as::<CollisionShape2D>("CollisionShape2D");
+        shape.set_disabled(!closed);
+    }
+}
+
 #[godot_dyn]
 impl TriggerableEnvObject for ClosingDoor {
     fn on_activated(&mut self) {
+        let closed = !self.is_closed;
+        record_node_state(&self.to_gd(), ObjectState::Door { closed });
         let mut shape = self
             .base()
             .get_node_as::<CollisionShape2D>("CollisionShape2D");
@@ -5,8 +5,13 @@ use godot::{
 };
 
 use crate::{
-    entities::{entity_stats::StatModifier, hit_reg::Hitbox},
+    entities::{
+        entity_stats::{ModifierKind, Stat, StatModifier},
+        hit_reg::Hitbox,
+        player::abilities::MovementAbility,
+    },
     utils::collision_layers::CollisionLayers,
+    world::world_state::{ObjectState, record_node_state},
 };
 
 #[derive(Default, Clone, Debug, PartialEq)]
@@ -21,6 +26,9 @@ pub enum ItemKind {
     RosaryBead {
         effect: StatModifier,
     },
+    Ability {
+        ability: MovementAbility,
+    },
 }
 
 #[derive(Default, Clone, Debug, PartialEq)]
@@ -40,6 +48,32 @@ impl Item {
             icon_path,
         }
     }
+
+    /// Looks up an item by id, for the dev console's `give` command.
+    pub fn from_id(id: &str) -> Option<Self> {
+        let ability = |ability, name: &str| {
+            Item::new(
+                ItemKind::Ability { ability },
+                name.to_string(),
+                None,
+                "res://assets/icon.svg".to_string(),
+            )
+        };
+        match id {
+            "double_jump" => Some(ability(MovementAbility::DoubleJump, "Double Jump")),
+            "air_dash" => Some(ability(MovementAbility::AirDash, "Air Dash")),
+            "wall_grab" => Some(ability(MovementAbility::WallGrab, "Wall Grab")),
+            "max_health_relic" => Some(Item::new(
+                ItemKind::Relic {
+                    effect: StatModifier::new(Stat::MaxHealth, ModifierKind::Flat(2)),
+                },
+                "Relic Increase Max Health".to_string(),
+                Some("A relic which, when equipped, increases max health".to_string()),
+                "res://assets/bullet.webp".to_string(),
+            )),
+            _ => None,
+        }
+    }
 }
 
 /// This struct is initialized by Godot in `Main::init_game_items`.
@@ -47,6 +81,11 @@ impl Item {
 #[derive(GodotClass, Clone)]
 #[class(base=Node2D)]
 pub struct GameItem {
+    // Work around Godot not supporting discriminated unions.
+    /// If set, picking up this item unlocks a movement ability.
+    /// 0 = None, 1 = DoubleJump, 2 = AirDash, 3 = WallGrab
+    #[export]
+    ability_hint: u32,
     pub item: Item,
     pub sig_handler: Option<Gd<GameItemSignalHandler>>,
 }
@@ -55,6 +94,7 @@ pub struct GameItem {
 impl INode2D for GameItem {
     fn init(_base: Base<Node2D>) -> Self {
         Self {
+            ability_hint: 0,
             item: Item::default(),
             sig_handler: None,
         }
@@ -103,6 +143,8 @@ impl GameItemSignalHandler {
     pub fn player_exited_item_area();
 
     pub fn destroy(&mut self) {
-        self.base().get_parent().unwrap().queue_free();
+        let mut item = self.base().get_parent().unwrap();
+        record_node_state(&item, ObjectState::ItemPicked);
+        item.queue_free();
     }
 }
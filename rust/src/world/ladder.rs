@@ -0,0 +1,49 @@
+use godot::{
+    classes::{Area2D, IArea2D},
+    obj::WithBaseField,
+    prelude::*,
+};
+
+use crate::{
+    entities::player::main_character::MainCharacter, utils::collision_layers::CollisionLayers,
+};
+
+/// A climbable area. The player grabs it by pressing up, or down while airborne, and is centered
+/// horizontally on the ladder while climbing.
+#[derive(GodotClass)]
+#[class(init, base = Area2D)]
+pub struct Ladder {
+    base: Base<Area2D>,
+}
+
+#[godot_api]
+impl IArea2D for Ladder {
+    fn ready(&mut self) {
+        self.base_mut()
+            .set_collision_layer_value(CollisionLayers::WorldEffects as i32, true);
+        self.base_mut()
+            .set_collision_mask_value(CollisionLayers::PlayerPhysics as i32, true);
+        self.signals()
+            .body_entered()
+            .connect_self(Self::on_body_entered);
+        self.signals()
+            .body_exited()
+            .connect_self(Self::on_body_exited);
+    }
+}
+
+#[godot_api]
+impl Ladder {
+    fn on_body_entered(&mut self, body: Gd<Node2D>) {
+        if let Ok(mut player) = body.try_cast::<MainCharacter>() {
+            let x = self.base().get_global_position().x;
+            player.bind_mut().enter_ladder(x);
+        }
+    }
+
+    fn on_body_exited(&mut self, body: Gd<Node2D>) {
+        if let Ok(mut player) = body.try_cast::<MainCharacter>() {
+            player.bind_mut().exit_ladder();
+        }
+    }
+}
@@ -8,25 +8,41 @@ use super::map::Map;
 
 use crate::{
     entities::player::{
+        abilities::MovementAbility,
         item_component::ItemComponent,
         main_character::MainCharacter,
         shaky_player_camera::{CameraData, PlayerCamera},
     },
-    utils::global_data_singleton::GlobalData,
-    world::item::{GameItem, GameItemSignalHandler},
+    ui::game_over_screen::GameOverScreen,
+    utils::{
+        global_data_singleton::GlobalData, input_bindings::InputBindings, save_data::SaveData,
+        time_control,
+    },
+    world::item::{GameItem, GameItemSignalHandler, Item, ItemKind},
 };
 
 #[derive(GodotClass)]
 #[class(init, base = Node)]
-struct Main {
+pub struct Main {
     #[init(val = OnReady::manual())]
     map: OnReady<Gd<Map>>,
+    #[init(node = "GameOverScreen")]
+    game_over_screen: OnReady<Gd<GameOverScreen>>,
     base: Base<Node>,
 }
 
 #[godot_api]
 impl INode for Main {
     fn ready(&mut self) {
+        InputBindings::load_and_apply();
+        let save = SaveData::load();
+        if let Some(save) = &save {
+            let mut data = GlobalData::singleton();
+            let mut data = data.bind_mut();
+            data.checkpoint = save.checkpoint.clone();
+            data.world_state = save.world_state.clone();
+        }
+
         self.map.init(
             self.base()
                 .get_node_as::<Map>(GlobalData::singleton().bind().paths.map.as_ref().unwrap()),
@@ -41,8 +57,20 @@ impl INode for Main {
             .map_ready()
             .connect_self(Self::fade_camera_in);
 
+        self.game_over_screen
+            .signals()
+            .respawn_requested()
+            .connect_other(&self.to_gd(), Self::on_respawn_requested);
+
         if let Some(path) = &GlobalData::singleton().bind().paths.player {
-            let player = self.base().get_node_as::<MainCharacter>(path);
+            let mut player = self.base().get_node_as::<MainCharacter>(path);
+            if let Some(save) = save {
+                player.bind_mut().abilities.merge(save.abilities);
+            }
+            player
+                .signals()
+                .died()
+                .connect_other(&self.to_gd(), Self::on_player_died);
 
             // Give the map's `CameraData` nodes a ref to the player's camera.
             let player_cam = player.get_node_as::<PlayerCamera>("ShakyPlayerCamera");
@@ -67,6 +95,10 @@ impl INode for Main {
             .connect(Main::on_player_exited_tree);
     }
 
+    fn physics_process(&mut self, _delta: f32) {
+        time_control::tick();
+    }
+
     fn process(&mut self, _delta: f32) {
         if let Some(path) = GlobalData::singleton().bind().paths.player.clone() {
             let mut player = self.base().get_node_as::<MainCharacter>(&path);
@@ -109,7 +141,57 @@ impl Main {
         }
     }
 
+    pub fn current_map(&self) -> Gd<Map> {
+        (*self.map).clone()
+    }
+
     fn on_transition_map_request(&mut self, next_map: Gd<PackedScene>) {
+        self.change_map(next_map, None);
+    }
+
+    fn on_player_died(&mut self) {
+        // Deferred, as the player is bound while emitting `died`.
+        self.run_deferred(|this| {
+            let cause = GlobalData::singleton()
+                .bind()
+                .player
+                .as_ref()
+                .and_then(|p| p.bind().resources.last_damage_source());
+            this.game_over_screen.bind_mut().show_screen(cause);
+        });
+    }
+
+    /// Respawns the player at the last checkpoint they rested at. If the player has not rested
+    /// yet, the current map is reloaded and the player respawns at the map's spawn position.
+    fn on_respawn_requested(&mut self) {
+        let checkpoint = GlobalData::singleton().bind().checkpoint.clone();
+        let (next_map, position) = match checkpoint {
+            Some(checkpoint) => (
+                load::<PackedScene>(&checkpoint.map_scene),
+                Some(checkpoint.position),
+            ),
+            None => (load::<PackedScene>(&self.map.get_scene_file_path()), None),
+        };
+
+        let mut player = self.base().get_node_as::<MainCharacter>(
+            GlobalData::singleton()
+                .bind()
+                .paths
+                .player
+                .as_ref()
+                .unwrap(),
+        );
+        player.bind_mut().respawn();
+        self.change_map(next_map, position);
+    }
+
+    /// Replaces the current map with `next_map`, placing the player at `spawn_position` or, if
+    /// `None`, at the new map's spawn position.
+    pub(crate) fn change_map(
+        &mut self,
+        next_map: Gd<PackedScene>,
+        spawn_position: Option<Vector2>,
+    ) {
         let mut this = self.to_gd();
         let future = self.fade_camera_out();
         godot::task::spawn(async move {
@@ -146,8 +228,8 @@ impl Main {
                 let timer = this.get_tree().unwrap().create_timer(0.1).unwrap();
 
                 new_map.signals().ready().to_future().await;
-                let pos = new_map.bind().player_spawn_pos;
-                player.set_position(pos);
+                let pos = spawn_position.unwrap_or(new_map.bind().player_spawn_pos);
+                player.set_global_position(pos);
 
                 // Wait for camera to move to player's position.
                 // Additional scope used to prevent double borrow of player.
@@ -228,6 +310,16 @@ impl Main {
             game_item.add_to_group("items");
             game_item.bind_mut().sig_handler = Some(node);
 
+            let hint = game_item.bind().get_ability_hint();
+            if let Some(ability) = MovementAbility::from_hint(hint) {
+                game_item.bind_mut().item = Item::new(
+                    ItemKind::Ability { ability },
+                    format!("{ability:?}"),
+                    None,
+                    "res://assets/icon.svg".to_string(),
+                );
+            }
+
             let area = game_item.get_node_as::<Area2D>("Area2D");
             let mut gi = game_item.clone();
             area.signals()
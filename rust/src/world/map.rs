@@ -4,14 +4,18 @@ use godot::{
 };
 
 use super::{
-    environment_trigger::{EnvironmentTrigger, MapTransition},
+    environment_trigger::{ClosingDoor, EnvironmentTrigger, MapTransition},
     item::GameItem,
+    world_state::ObjectState,
 };
 use crate::{
     entities::player::shaky_player_camera::CameraData, utils::global_data_singleton::GlobalData,
     world::environment_trigger::SceneTransition,
 };
 
+/// Enemies in this group are not respawned when the player rests.
+pub const BOSS_GROUP: &str = "boss";
+
 #[derive(GodotClass)]
 #[class(base = Node, init)]
 pub struct Map {
@@ -22,6 +26,9 @@ pub struct Map {
     pub items: Vec<Gd<GameItem>>,
     pub scene_trans: Vec<Gd<SceneTransition>>,
     pub camera_data: Vec<Gd<CameraData>>,
+    /// Copies of the map's enemies as they were when the map was loaded. Used to respawn enemies.
+    /// Enemies in the "boss" group are excluded.
+    enemy_templates: Vec<Gd<Node>>,
 
     base: Base<Node>,
 }
@@ -37,10 +44,20 @@ impl INode for Map {
     }
 
     fn exit_tree(&mut self) {
-        GlobalData::singleton().bind_mut().paths.map.take();
+        // The next map enters the tree before this map exits during map transitions.
+        let path = self.base().get_path().to_string();
+        let mut data = GlobalData::singleton();
+        if data.bind().paths.map.as_ref() == Some(&path) {
+            data.bind_mut().paths.map.take();
+        }
+        for template in self.enemy_templates.drain(..) {
+            template.free();
+        }
     }
 
     fn ready(&mut self) {
+        self.restore_world_state();
+
         self.player_spawn_pos = self
             .base()
             .get_node_as::<Marker2D>("PlayerSpawnPos")
@@ -79,6 +96,13 @@ impl INode for Map {
 
         let camera_data = self.base().get_node_as::<Node>("CameraData").get_children();
         self.camera_data = camera_data.iter_shared().map(Gd::cast).collect();
+
+        let enemies = self.base().get_node_as::<Node>("Enemies").get_children();
+        self.enemy_templates = enemies
+            .iter_shared()
+            .filter(|e| !e.is_in_group(BOSS_GROUP))
+            .filter_map(|e| e.duplicate())
+            .collect();
     }
 }
 
@@ -87,6 +111,66 @@ impl Map {
     #[signal]
     pub fn propigate_map_trans(next_map: Gd<PackedScene>);
 
+    /// Frees all non-boss enemies and replaces them with fresh copies of the map's original
+    /// enemies.
+    pub fn respawn_enemies(&mut self) {
+        let mut enemies = self.base().get_node_as::<Node>("Enemies");
+        let fresh: Vec<Gd<Node>> = self
+            .enemy_templates
+            .iter()
+            .filter_map(|t| t.duplicate())
+            .collect();
+
+        // Deferred, as this may be called during a physics callback.
+        enemies.run_deferred_gd(move |mut enemies| {
+            for mut enemy in enemies.get_children().iter_shared() {
+                if !enemy.is_in_group(BOSS_GROUP) {
+                    enemies.remove_child(&enemy);
+                    enemy.queue_free();
+                }
+            }
+            for enemy in fresh {
+                enemies.add_child(&enemy);
+            }
+        });
+    }
+
+    /// Applies the recorded world state of this map, removing picked up items, consumed triggers
+    /// and defeated bosses, and moving doors to their last position.
+    fn restore_world_state(&mut self) {
+        let map_id = self.base().get_scene_file_path().to_string();
+        let states = GlobalData::singleton()
+            .bind()
+            .world_state
+            .map_states(&map_id);
+
+        for (object_id, state) in states {
+            let Some(node) = self.base().get_node_or_null(object_id.as_str()) else {
+                continue;
+            };
+            match state {
+                ObjectState::ItemPicked
+                | ObjectState::TriggerConsumed
+                | ObjectState::BossDefeated => {
+                    if let Some(mut parent) = node.get_parent() {
+                        parent.remove_child(&node);
+                    }
+                    node.free();
+                }
+                ObjectState::Door { closed } => {
+                    if let Ok(mut door) = node.try_cast::<ClosingDoor>() {
+                        door.bind_mut().set_closed(closed);
+                    }
+                }
+                ObjectState::TriggerRemaining(remaining) => {
+                    if let Ok(mut trigger) = node.try_cast::<EnvironmentTrigger>() {
+                        trigger.bind_mut().set_remaining_triggers(remaining);
+                    }
+                }
+            }
+        }
+    }
+
     // Propigate the transition request to the main node.
     fn on_map_transition_req(&mut self, next_map: Gd<PackedScene>) {
         self.signals().propigate_map_trans().emit(&next_map);
@@ -1,6 +1,11 @@
+pub mod checkpoint;
 mod enemy_spawner;
 pub mod environment_trigger;
 #[allow(unused)]
 pub mod item;
-mod main_node;
-mod map;
+pub mod ladder;
+pub mod main_node;
+pub mod map;
+pub mod moving_platform;
+pub mod water_volume;
+pub mod world_state;
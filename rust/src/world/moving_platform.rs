@@ -0,0 +1,199 @@
+use godot::{
+    classes::{AnimatableBody2D, IAnimatableBody2D, Path2D},
+    prelude::*,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, GodotConvert, Var, Export)]
+#[godot(via = i64)]
+pub enum PathMode {
+    /// Travels to the last point, then back to the first.
+    #[default]
+    PingPong,
+    /// Travels from the last point directly to the first.
+    Loop,
+}
+
+/// Moves a position along a list of global points at a constant speed, waiting at each point.
+#[derive(Clone, Debug, Default)]
+pub struct PlatformMotion {
+    points: Vec<Vector2>,
+    mode: PathMode,
+    speed: f32,
+    wait_time: f32,
+    target: usize,
+    forward: bool,
+    waiting: f32,
+}
+
+impl PlatformMotion {
+    pub fn new(points: Vec<Vector2>, mode: PathMode, speed: f32, wait_time: f32) -> Self {
+        Self {
+            target: usize::from(points.len() > 1),
+            points,
+            mode,
+            speed,
+            wait_time,
+            forward: true,
+            waiting: 0.0,
+        }
+    }
+
+    /// Returns the next position after moving from `position` for `delta` seconds.
+    pub fn step(&mut self, position: Vector2, delta: f32) -> Vector2 {
+        if self.points.len() < 2 {
+            return position;
+        }
+        if self.waiting > 0.0 {
+            self.waiting -= delta;
+            return position;
+        }
+
+        let target = self.points[self.target];
+        let next = position.move_toward(target, self.speed * delta);
+        if next == target {
+            self.waiting = self.wait_time;
+            if self.mode == PathMode::PingPong
+                && (self.forward && self.target == self.points.len() - 1
+                    || !self.forward && self.target == 0)
+            {
+                self.forward = !self.forward;
+            }
+            self.target = self.neighbour(self.target);
+        }
+        next
+    }
+
+    /// Turns around and heads back to the previous point.
+    pub fn reverse(&mut self) {
+        if self.points.len() < 2 {
+            return;
+        }
+        self.forward = !self.forward;
+        self.target = self.neighbour(self.target);
+    }
+
+    fn neighbour(&self, index: usize) -> usize {
+        let len = self.points.len();
+        match (self.mode, self.forward) {
+            (PathMode::Loop, true) => (index + 1) % len,
+            (PathMode::Loop, false) => (index + len - 1) % len,
+            (PathMode::PingPong, true) => (index + 1).min(len - 1),
+            (PathMode::PingPong, false) => index.saturating_sub(1),
+        }
+    }
+}
+
+/// A platform that follows a `Path2D` or a list of waypoints. Bodies standing on it are carried
+/// along by `move_and_slide`. Entities that move by setting their position ask for `velocity`
+/// instead.
+#[derive(GodotClass)]
+#[class(init, base = AnimatableBody2D)]
+pub struct MovingPlatform {
+    /// If set, the platform follows the points of this path's curve instead of `waypoints`.
+    #[export]
+    path: Option<Gd<Path2D>>,
+
+    /// Points relative to the platform's starting position.
+    #[export]
+    waypoints: PackedVector2Array,
+
+    #[export]
+    path_mode: PathMode,
+
+    #[export]
+    #[init(val = 60.0)]
+    speed: f32,
+
+    /// Seconds to wait at each point.
+    #[export]
+    wait_time: f32,
+
+    motion: PlatformMotion,
+    /// Velocity of the last physics frame.
+    velocity: Vector2,
+    base: Base<AnimatableBody2D>,
+}
+
+#[godot_api]
+impl IAnimatableBody2D for MovingPlatform {
+    fn ready(&mut self) {
+        let start = self.base().get_global_position();
+        let points = if let Some(path) = &self.path
+            && let Some(curve) = path.get_curve()
+        {
+            (0..curve.get_point_count())
+                .map(|i| path.to_global(curve.get_point_position(i)))
+                .collect()
+        } else {
+            std::iter::once(start)
+                .chain(self.waypoints.as_slice().iter().map(|p| start + *p))
+                .collect()
+        };
+
+        self.motion = PlatformMotion::new(points, self.path_mode, self.speed, self.wait_time);
+    }
+
+    fn physics_process(&mut self, delta: f32) {
+        let position = self.base().get_global_position();
+        let next = self.motion.step(position, delta);
+        self.velocity = (next - position) / delta;
+        self.base_mut().set_global_position(next);
+    }
+}
+
+impl MovingPlatform {
+    pub fn velocity(&self) -> Vector2 {
+        self.velocity
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use godot::builtin::Vector2;
+
+    use super::{PathMode, PlatformMotion};
+
+    fn run(motion: &mut PlatformMotion, steps: usize) -> Vec<Vector2> {
+        let mut position = Vector2::ZERO;
+        (0..steps)
+            .map(|_| {
+                position = motion.step(position, 1.0);
+                position
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_platform_motion_ping_pong() {
+        let points = vec![
+            Vector2::ZERO,
+            Vector2::new(10.0, 0.0),
+            Vector2::new(20.0, 0.0),
+        ];
+        let mut motion = PlatformMotion::new(points, PathMode::PingPong, 10.0, 0.0);
+        let xs: Vec<f32> = run(&mut motion, 6).iter().map(|p| p.x).collect();
+        assert_eq!(xs, [10.0, 20.0, 10.0, 0.0, 10.0, 20.0]);
+    }
+
+    #[test]
+    fn test_platform_motion_loop_and_wait() {
+        let points = vec![
+            Vector2::ZERO,
+            Vector2::new(10.0, 0.0),
+            Vector2::new(20.0, 0.0),
+        ];
+        let mut motion = PlatformMotion::new(points, PathMode::Loop, 10.0, 1.0);
+        let xs: Vec<f32> = run(&mut motion, 6).iter().map(|p| p.x).collect();
+        assert_eq!(xs, [10.0, 10.0, 20.0, 20.0, 10.0, 0.0]);
+    }
+
+    #[test]
+    fn test_platform_motion_reverse() {
+        let points = vec![Vector2::ZERO, Vector2::new(10.0, 0.0)];
+        let mut motion = PlatformMotion::new(points, PathMode::PingPong, 5.0, 0.0);
+        let mut position = motion.step(Vector2::ZERO, 1.0);
+        motion.reverse();
+        position = motion.step(position, 1.0);
+        assert_eq!(position, Vector2::ZERO);
+    }
+}
@@ -0,0 +1,80 @@
+use godot::{
+    classes::{Area2D, IArea2D},
+    obj::WithBaseField,
+    prelude::*,
+};
+
+use crate::{
+    entities::{
+        combat::offense::Damage,
+        player::{main_character::MainCharacter, physics::WaterProperties},
+    },
+    utils::collision_layers::CollisionLayers,
+};
+
+/// A body of water the player swims in. Slows movement, reduces gravity and optionally drowns the
+/// player if they stay submerged for too long.
+#[derive(GodotClass)]
+#[class(init, base = Area2D)]
+pub struct WaterVolume {
+    #[export]
+    #[init(val = 0.3)]
+    gravity_scale: f32,
+    #[export]
+    #[init(val = 0.6)]
+    speed_scale: f32,
+    /// Upward acceleration. Floats the player when greater than the scaled gravity.
+    #[export]
+    #[init(val = 250.0)]
+    buoyancy: f32,
+    #[export]
+    #[init(val = 150.0)]
+    max_vertical_speed: f32,
+    /// Seconds the player can stay submerged before taking damage. 0 disables drowning.
+    #[export]
+    drowning_time: f64,
+    /// Damage taken every `drowning_time` seconds once the player runs out of breath.
+    #[export]
+    #[init(val = 5)]
+    drowning_damage: i64,
+    base: Base<Area2D>,
+}
+
+#[godot_api]
+impl IArea2D for WaterVolume {
+    fn ready(&mut self) {
+        self.base_mut()
+            .set_collision_layer_value(CollisionLayers::WorldEffects as i32, true);
+        self.base_mut()
+            .set_collision_mask_value(CollisionLayers::PlayerPhysics as i32, true);
+        self.signals()
+            .body_entered()
+            .connect_self(Self::on_body_entered);
+        self.signals()
+            .body_exited()
+            .connect_self(Self::on_body_exited);
+    }
+}
+
+#[godot_api]
+impl WaterVolume {
+    fn on_body_entered(&mut self, body: Gd<Node2D>) {
+        if let Ok(mut player) = body.try_cast::<MainCharacter>() {
+            let water = WaterProperties {
+                gravity_scale: self.gravity_scale,
+                speed_scale: self.speed_scale,
+                buoyancy: self.buoyancy,
+                max_vertical_speed: self.max_vertical_speed,
+            };
+            let drowning = (self.drowning_time > 0.0)
+                .then_some((self.drowning_time, Damage(self.drowning_damage)));
+            player.bind_mut().enter_water(water, drowning);
+        }
+    }
+
+    fn on_body_exited(&mut self, body: Gd<Node2D>) {
+        if let Ok(mut player) = body.try_cast::<MainCharacter>() {
+            player.bind_mut().exit_water();
+        }
+    }
+}
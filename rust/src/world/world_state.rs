@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+
+use godot::{
+    classes::Node,
+    obj::{Gd, Inherits},
+};
+
+use super::map::Map;
+use crate::utils::global_data_singleton::GlobalData;
+
+/// The persisted state of an object within a map.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ObjectState {
+    ItemPicked,
+    Door { closed: bool },
+    TriggerConsumed,
+    TriggerRemaining(u32),
+    BossDefeated,
+}
+
+impl ObjectState {
+    /// Converts `Self` into a kind and value pair, used for saving.
+    pub fn to_parts(self) -> (&'static str, i64) {
+        match self {
+            ObjectState::ItemPicked => ("item_picked", 0),
+            ObjectState::Door { closed } => ("door", i64::from(closed)),
+            ObjectState::TriggerConsumed => ("trigger_consumed", 0),
+            ObjectState::TriggerRemaining(n) => ("trigger_remaining", i64::from(n)),
+            ObjectState::BossDefeated => ("boss_defeated", 0),
+        }
+    }
+
+    /// The inverse of `to_parts`.
+    pub fn from_parts(kind: &str, value: i64) -> Option<Self> {
+        match kind {
+            "item_picked" => Some(ObjectState::ItemPicked),
+            "door" => Some(ObjectState::Door { closed: value != 0 }),
+            "trigger_consumed" => Some(ObjectState::TriggerConsumed),
+            "trigger_remaining" => u32::try_from(value).ok().map(ObjectState::TriggerRemaining),
+            "boss_defeated" => Some(ObjectState::BossDefeated),
+            _ => None,
+        }
+    }
+}
+
+/// Identifies an object by the scene file path of the map it belongs to and its node path
+/// relative to that map.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ObjectKey {
+    pub map_id: String,
+    pub object_id: String,
+}
+
+/// Registry of map object states, so that changes to a map survive map transitions and restarts.
+#[derive(Default, Clone, Debug)]
+pub struct WorldState(HashMap<ObjectKey, ObjectState>);
+
+impl WorldState {
+    /// Records the state of an object, replacing any previous state.
+    pub fn record(&mut self, map_id: &str, object_id: &str, state: ObjectState) {
+        self.0.insert(
+            ObjectKey {
+                map_id: map_id.to_string(),
+                object_id: object_id.to_string(),
+            },
+            state,
+        );
+    }
+
+    /// All recorded object states of the given map as (object ID, state) pairs.
+    pub fn map_states(&self, map_id: &str) -> Vec<(String, ObjectState)> {
+        self.0
+            .iter()
+            .filter(|(k, _)| k.map_id == map_id)
+            .map(|(k, v)| (k.object_id.clone(), *v))
+            .collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&ObjectKey, &ObjectState)> {
+        self.0.iter()
+    }
+}
+
+/// Records the state of `node` in the `GlobalData` world state. The map and object IDs are taken
+/// from the nearest `Map` ancestor of `node`. Does nothing if `node` is not part of a map.
+pub fn record_node_state(node: &Gd<impl Inherits<Node>>, state: ObjectState) {
+    let node = node.clone().upcast::<Node>();
+    let mut parent = node.get_parent();
+    while let Some(p) = parent {
+        match p.try_cast::<Map>() {
+            Ok(map) => {
+                let map_id = map.get_scene_file_path().to_string();
+                let object_id = map.get_path_to(&node).to_string();
+                GlobalData::singleton()
+                    .bind_mut()
+                    .world_state
+                    .record(&map_id, &object_id, state);
+                return;
+            }
+            Err(p) => parent = p.get_parent(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ObjectState, WorldState};
+
+    #[test]
+    fn test_world_state_is_keyed_by_map_and_object() {
+        let mut state = WorldState::default();
+        state.record("res://a.tscn", "Items/GameItem", ObjectState::ItemPicked);
+        state.record("res://b.tscn", "Items/GameItem", ObjectState::BossDefeated);
+        state.record("res://a.tscn", "Door", ObjectState::Door { closed: true });
+        state.record("res://a.tscn", "Door", ObjectState::Door { closed: false });
+
+        let mut a_states = state.map_states("res://a.tscn");
+        a_states.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            a_states,
+            vec![
+                ("Door".to_string(), ObjectState::Door { closed: false }),
+                ("Items/GameItem".to_string(), ObjectState::ItemPicked),
+            ]
+        );
+        assert!(state.map_states("res://c.tscn").is_empty());
+    }
+
+    #[test]
+    fn test_object_state_parts_round_trip() {
+        let states = [
+            ObjectState::ItemPicked,
+            ObjectState::Door { closed: true },
+            ObjectState::Door { closed: false },
+            ObjectState::TriggerConsumed,
+            ObjectState::TriggerRemaining(3),
+            ObjectState::BossDefeated,
+        ];
+        for state in states {
+            let (kind, value) = state.to_parts();
+            assert_eq!(ObjectState::from_parts(kind, value), Some(state));
+        }
+        assert_eq!(ObjectState::from_parts("unknown", 0), None);
+    }
+}